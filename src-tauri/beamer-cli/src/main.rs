@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use beamer_core::library::{Library, LIBRARY_DIR};
+use beamer_core::querying::search_song;
+use beamer_core::setlist::validate_song_list;
+use beamer_core::song::{Song, SongList};
+use beamer_core::song_import::import_song_file;
+
+/// Same as `identifier` in `tauri.conf.json`, so the tool works on the files of the app.
+const APP_IDENTIFIER: &str = "nielsgroeneveld.beamersoftware";
+
+/// The options that take a value. Anything else starting with `--` is rejected.
+const VALUE_OPTIONS: &[&str] = &["library", "config", "format", "output"];
+
+const USAGE: &str = "\
+Usage: beamer [--library <dir>] [--config <file>] <command>
+
+Commands:
+  import <file>...             Add songs to the library: .txt, ChordPro (.cho, .chordpro, .chopro, .crd, .pro)
+                               or OpenLyrics (.xml)
+  list                         List the songs in the library
+  search <query>               List the songs with <query> in the title, author or lyrics
+  export <title> [--format text|chordpro|json] [--output <file>]
+                               Print a song from the library, or write it to <file>
+  validate <file>...           Check song list files, as sent to the remote API
+  fetch <author> <title>       Load a song from Genius into the library, with the token from config.json
+
+Options:
+  --library <dir>              The library folder, instead of the one of the app
+  --config <file>              The config file, instead of the one of the app";
+
+/// The part of the app config the tool uses.
+#[derive(Deserialize, Default)]
+struct Config {
+    genius_api_token: Option<String>,
+}
+
+/// Positional arguments, and the values of `--name value` options.
+struct Arguments {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+fn main() {
+    if std::env::args().any(|x| x == "--help" || x == "-h") {
+        println!("{USAGE}");
+        return;
+    }
+
+    let arguments = match parse_arguments(std::env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            std::process::exit(2);
+        },
+    };
+
+    match run(arguments) {
+        Ok(true) => {},
+        Ok(false) => std::process::exit(1),
+        Err(error) => {
+            eprintln!("error: {error}");
+            std::process::exit(1);
+        },
+    }
+}
+
+fn parse_arguments(args: impl Iterator<Item = String>) -> Result<Arguments, String> {
+    let mut arguments = Arguments {
+        positional: vec![],
+        options: HashMap::new(),
+    };
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        if let Some(name) = arg.strip_prefix("--") {
+            if !VALUE_OPTIONS.contains(&name) {
+                return Err(format!("Unknown option --{name}"));
+            }
+            let value = args.next().ok_or(format!("--{name} needs a value"))?;
+            arguments.options.insert(name.to_string(), value);
+        } else {
+            arguments.positional.push(arg);
+        }
+    }
+
+    Ok(arguments)
+}
+
+/// Returns whether everything succeeded, for the exit code.
+fn run(arguments: Arguments) -> Result<bool, String> {
+    let library = Library::new(match arguments.options.get("library") {
+        Some(dir) => PathBuf::from(dir),
+        None => app_dir(dirs_next::data_dir())?.join(LIBRARY_DIR),
+    });
+
+    let (command, parameters) = arguments.positional.split_first().ok_or("No command given".to_string())?;
+    match (command.as_str(), parameters) {
+        ("import", files) if !files.is_empty() => import(&library, files),
+        ("list", []) => {
+            print_songs(&library.songs()?);
+            Ok(true)
+        },
+        ("search", [query]) => {
+            print_songs(&library.search(query)?);
+            Ok(true)
+        },
+        ("export", [title]) => {
+            export(
+                &library.find(title)?,
+                arguments.options.get("format").map(|x| x.as_str()).unwrap_or("text"),
+                arguments.options.get("output").map(Path::new),
+            )?;
+            Ok(true)
+        },
+        ("validate", files) if !files.is_empty() => Ok(validate(files)),
+        ("fetch", [author, title]) => {
+            let config_path = match arguments.options.get("config") {
+                Some(path) => PathBuf::from(path),
+                None => app_dir(dirs_next::config_dir())?.join("config.json"),
+            };
+            fetch(&library, &config_path, author, title)?;
+            Ok(true)
+        },
+        _ => Err(format!("Unknown command or wrong number of arguments: {}\n\n{USAGE}", arguments.positional.join(" "))),
+    }
+}
+
+/// Where the app keeps its files, e.g. `~/.local/share/nielsgroeneveld.beamersoftware`.
+fn app_dir(base_dir: Option<PathBuf>) -> Result<PathBuf, String> {
+    base_dir
+        .map(|x| x.join(APP_IDENTIFIER))
+        .ok_or("No app directory found, use --library and --config".to_string())
+}
+
+/// Keeps going when a file can't be imported, so one bad file does not stop a bulk import.
+fn import(library: &Library, files: &[String]) -> Result<bool, String> {
+    let mut all_imported = true;
+
+    for file in files {
+        match import_song_file(Path::new(file)).and_then(|song| library.add(&song).map(|_| song)) {
+            Ok(song) => println!("Imported '{}' from {file}", song.title),
+            Err(error) => {
+                eprintln!("{file}: {error}");
+                all_imported = false;
+            },
+        }
+    }
+
+    Ok(all_imported)
+}
+
+fn print_songs(songs: &[Song]) {
+    for song in songs {
+        if song.author.is_empty() {
+            println!("{}", song.title);
+        } else {
+            println!("{} - {}", song.title, song.author);
+        }
+    }
+}
+
+fn export(song: &Song, format: &str, output: Option<&Path>) -> Result<(), String> {
+    let exported = match format {
+        "text" => song.to_text(),
+        "chordpro" => song.to_chordpro(),
+        "json" => serde_json::to_string_pretty(song).map_err(|_| "Unable to convert song to JSON".to_string())?,
+        _ => return Err(format!("Unknown format '{format}': expected text, chordpro or json")),
+    };
+
+    match output {
+        Some(path) => fs::write(path, exported).map_err(|_| format!("Unable to write to {}", path.display())),
+        None => {
+            println!("{exported}");
+            Ok(())
+        },
+    }
+}
+
+/// Reports every problem in every file. Returns whether all files are valid.
+fn validate(files: &[String]) -> bool {
+    let mut all_valid = true;
+
+    for file in files {
+        let problems = match fs::read_to_string(file) {
+            Ok(json) => match serde_json::from_str::<SongList>(&json) {
+                Ok(song_list) => validate_song_list(&song_list),
+                Err(error) => vec![format!("Not a song list: {error}")],
+            },
+            Err(_) => vec!["Unable to read file".to_string()],
+        };
+
+        if problems.is_empty() {
+            println!("{file}: ok");
+        } else {
+            all_valid = false;
+            for problem in problems {
+                println!("{file}: {problem}");
+            }
+        }
+    }
+
+    all_valid
+}
+
+fn fetch(library: &Library, config_path: &Path, author: &str, title: &str) -> Result<(), String> {
+    let config: Config = match fs::read_to_string(config_path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|_| format!("{} is not a valid config", config_path.display()))?,
+        Err(_) => Config::default(),
+    };
+    let genius_token = config.genius_api_token
+        .filter(|x| !x.is_empty())
+        .ok_or(format!("No Genius API token in {}", config_path.display()))?;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|x| x.to_string())?;
+    let song = runtime.block_on(search_song(author, title, &genius_token))?;
+
+    let path = library.add(&song)?;
+    println!("Added '{}' by {} to {}", song.title, song.author, path.display());
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use crate::bible_reference::{book_english_name, BibleReference};
+use crate::song::Verse;
+
+/// Verses are kept together on a slide until it would contain more characters than this.
+const MAX_SLIDE_CHARACTERS: usize = 350;
+
+/// An imported bible translation, stored as JSON in the `bibles` folder of the app data dir.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bible {
+    pub name: String,
+    pub books: Vec<BibleBook>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BibleBook {
+    pub number: u32,
+    pub name: String,
+    pub chapters: Vec<BibleChapter>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BibleChapter {
+    pub number: u32,
+    pub verses: Vec<BibleVerse>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BibleVerse {
+    pub number: u32,
+    pub text: String,
+}
+
+impl Bible {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            books: vec![],
+        }
+    }
+
+    pub fn add_verse(&mut self, book: u32, book_name: Option<&str>, chapter: u32, verse: BibleVerse) {
+        let book_position = match self.books.iter().rposition(|x| x.number == book) {
+            Some(position) => position,
+            None => {
+                let name = book_name
+                    .map(|x| x.to_string())
+                    .or_else(|| book_english_name(book).map(|x| x.to_string()))
+                    .unwrap_or_else(|| book.to_string());
+
+                self.books.push(BibleBook {
+                    number: book,
+                    name,
+                    chapters: vec![],
+                });
+                self.books.len() - 1
+            },
+        };
+        let chapters = &mut self.books[book_position].chapters;
+
+        let chapter_position = match chapters.iter().rposition(|x| x.number == chapter) {
+            Some(position) => position,
+            None => {
+                chapters.push(BibleChapter {
+                    number: chapter,
+                    verses: vec![],
+                });
+                chapters.len() - 1
+            },
+        };
+
+        chapters[chapter_position].verses.push(verse);
+    }
+
+    /// All verses in the reference, with their chapter number.
+    pub fn passage(&self, reference: &BibleReference) -> Result<Vec<(u32, &BibleVerse)>, String> {
+        let book = self.books.iter()
+            .find(|x| x.number == reference.book)
+            .ok_or(format!("{} does not contain this book", self.name))?;
+
+        let start = (reference.start_chapter, reference.start_verse.unwrap_or(0));
+        let end = (reference.end_chapter, reference.end_verse.unwrap_or(u32::MAX));
+
+        let verses = book.chapters.iter()
+            .flat_map(|chapter| chapter.verses.iter().map(move |verse| (chapter.number, verse)))
+            .filter(|(chapter, verse)| start <= (*chapter, verse.number) && (*chapter, verse.number) <= end)
+            .collect::<Vec<_>>();
+
+        if verses.is_empty() {
+            return Err(format!("{} does not contain {}", self.name, reference.range_text()));
+        }
+
+        Ok(verses)
+    }
+
+    pub fn book_name(&self, book: u32) -> String {
+        self.books.iter()
+            .find(|x| x.number == book)
+            .map(|x| x.name.clone())
+            .or_else(|| book_english_name(book).map(|x| x.to_string()))
+            .unwrap_or_default()
+    }
+}
+
+/// Splits a passage into slides, starting each verse with its number.
+/// The chapter is added to every number when the passage spans multiple chapters.
+pub fn passage_to_slides(verses: &[(u32, &BibleVerse)]) -> Vec<Verse> {
+    let mut slides = vec![];
+    let mut current_lines: Vec<String> = vec![];
+    let mut current_length = 0;
+    let spans_chapters = verses.windows(2).any(|x| x[0].0 != x[1].0);
+
+    for (chapter, verse) in verses {
+        let line = if spans_chapters {
+            format!("{}:{} {}", chapter, verse.number, verse.text)
+        } else {
+            format!("{} {}", verse.number, verse.text)
+        };
+
+        if !current_lines.is_empty() && current_length + line.len() > MAX_SLIDE_CHARACTERS {
+            slides.push(Verse::new(current_lines));
+            current_lines = vec![];
+            current_length = 0;
+        }
+
+        current_length += line.len();
+        current_lines.push(line);
+    }
+
+    if !current_lines.is_empty() {
+        slides.push(Verse::new(current_lines));
+    }
+
+    if slides.is_empty() {
+        slides.push(Verse::default());
+    }
+
+    slides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verse(number: u32, text: &str) -> BibleVerse {
+        BibleVerse { number, text: text.to_string() }
+    }
+
+    fn lines(slides: &[Verse]) -> Vec<Vec<&str>> {
+        slides.iter()
+            .map(|slide| slide.lines.iter().map(|x| x.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn passage_to_slides_numbers_verses_within_a_chapter() {
+        let (first, second) = (verse(16, "For God so loved"), verse(17, "For God sent not"));
+        let slides = passage_to_slides(&[(3, &first), (3, &second)]);
+
+        assert_eq!(lines(&slides), vec![vec!["16 For God so loved", "17 For God sent not"]]);
+    }
+
+    #[test]
+    fn passage_to_slides_adds_the_chapter_to_every_verse_across_chapters() {
+        let verses = [verse(35, "a"), verse(36, "b"), verse(1, "c"), verse(2, "d")];
+        let slides = passage_to_slides(&[(1, &verses[0]), (1, &verses[1]), (2, &verses[2]), (2, &verses[3])]);
+
+        assert_eq!(lines(&slides), vec![vec!["1:35 a", "1:36 b", "2:1 c", "2:2 d"]]);
+    }
+
+    #[test]
+    fn passage_to_slides_starts_a_new_slide_when_it_gets_too_long() {
+        let long_text = "word ".repeat(MAX_SLIDE_CHARACTERS / 10);
+        let verses = [verse(1, &long_text), verse(2, &long_text), verse(3, "short")];
+        let slides = passage_to_slides(&[(1, &verses[0]), (1, &verses[1]), (1, &verses[2])]);
+
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].lines.len(), 1);
+        assert_eq!(slides[1].lines.len(), 2);
+        assert_eq!(passage_to_slides(&[]), vec![Verse::default()]);
+    }
+}
@@ -0,0 +1,280 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use crate::bible::{Bible, BibleVerse};
+use crate::bible_reference::book_number_from_osis_id;
+use crate::xml::{attribute, collapse_whitespace};
+
+/// Imports a Zefania XML or OSIS bible, detecting the format from the root element.
+pub fn import_bible_xml(xml: &str, fallback_name: &str) -> Result<Bible, String> {
+    let mut reader = Reader::from_str(xml);
+
+    loop {
+        match reader.read_event().map_err(|x| format!("Invalid XML: {x}"))? {
+            Event::Start(element) => {
+                let name = element.local_name();
+                return if name.as_ref().eq_ignore_ascii_case(b"XMLBIBLE") {
+                    import_zefania(xml, fallback_name)
+                } else if name.as_ref().eq_ignore_ascii_case(b"osis") {
+                    import_osis(xml, fallback_name)
+                } else {
+                    Err("Unknown bible format: expected Zefania XML or OSIS".to_string())
+                };
+            },
+            Event::Eof => return Err("Empty bible file".to_string()),
+            _ => {},
+        }
+    }
+}
+
+/// Zefania XML: `<XMLBIBLE><BIBLEBOOK bnumber bname><CHAPTER cnumber><VERS vnumber>`.
+pub fn import_zefania(xml: &str, fallback_name: &str) -> Result<Bible, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut bible = Bible::new(fallback_name);
+
+    let mut book: Option<(u32, Option<String>)> = None;
+    let mut chapter: Option<u32> = None;
+    let mut verse: Option<u32> = None;
+    let mut verse_text = String::new();
+    // Depth inside elements whose text is not part of the verse, e.g. footnotes
+    let mut skip_depth = 0;
+
+    loop {
+        match reader.read_event().map_err(|x| format!("Invalid XML: {x}"))? {
+            Event::Start(element) => {
+                let name = element.local_name();
+                let name = String::from_utf8_lossy(name.as_ref()).to_uppercase();
+
+                match name.as_str() {
+                    "XMLBIBLE" => {
+                        if let Some(bible_name) = attribute(&element, "biblename") {
+                            bible.name = bible_name;
+                        }
+                    },
+                    "BIBLEBOOK" => {
+                        let number = attribute(&element, "bnumber").and_then(|x| x.parse().ok());
+                        book = number.map(|x| (x, attribute(&element, "bname")));
+                    },
+                    "CHAPTER" => chapter = attribute(&element, "cnumber").and_then(|x| x.parse().ok()),
+                    "VERS" => {
+                        verse = attribute(&element, "vnumber").and_then(|x| x.parse().ok());
+                        verse_text.clear();
+                    },
+                    "NOTE" | "CAPTION" | "REMARK" | "XREF" => skip_depth += 1,
+                    _ => {},
+                }
+            },
+            Event::End(element) => {
+                let name = element.local_name();
+                let name = String::from_utf8_lossy(name.as_ref()).to_uppercase();
+
+                match name.as_str() {
+                    "VERS" => {
+                        if let (Some((book_number, book_name)), Some(chapter), Some(verse)) = (&book, chapter, verse) {
+                            bible.add_verse(*book_number, book_name.as_deref(), chapter, BibleVerse {
+                                number: verse,
+                                text: collapse_whitespace(&verse_text),
+                            });
+                        }
+                        verse = None;
+                    },
+                    "NOTE" | "CAPTION" | "REMARK" | "XREF" => skip_depth -= 1,
+                    _ => {},
+                }
+            },
+            Event::Text(text) if verse.is_some() && skip_depth == 0 => {
+                verse_text.push_str(&text.unescape().map_err(|x| format!("Invalid XML: {x}"))?);
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    if bible.books.is_empty() {
+        return Err("No verses found in Zefania file".to_string());
+    }
+
+    Ok(bible)
+}
+
+/// OSIS: verses are either containers (`<verse osisID="John.3.16">...</verse>`)
+/// or milestones (`<verse sID="John.3.16" osisID="John.3.16"/>...<verse eID="John.3.16"/>`).
+pub fn import_osis(xml: &str, fallback_name: &str) -> Result<Bible, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut bible = Bible::new(fallback_name);
+
+    // (book, chapter, verse) of the verse currently being read
+    let mut verse: Option<(u32, u32, u32)> = None;
+    let mut verse_text = String::new();
+    let mut skip_depth = 0;
+    let mut in_header = false;
+    let mut in_work_title = false;
+
+    loop {
+        let event = reader.read_event().map_err(|x| format!("Invalid XML: {x}"))?;
+
+        match event {
+            Event::Start(ref element) | Event::Empty(ref element) => {
+                let is_empty = matches!(event, Event::Empty(_));
+
+                match element.local_name().as_ref() {
+                    b"verse" => {
+                        // Both a new start and an explicit end finish the previous verse
+                        if let Some((book, chapter, number)) = verse.take() {
+                            bible.add_verse(book, None, chapter, BibleVerse {
+                                number,
+                                text: collapse_whitespace(&verse_text),
+                            });
+                        }
+                        verse_text.clear();
+
+                        if attribute(element, "eID").is_none() {
+                            verse = attribute(element, "osisID")
+                                .or_else(|| attribute(element, "sID"))
+                                .and_then(|x| parse_osis_id(&x));
+                        }
+                    },
+                    b"header" if !is_empty => in_header = true,
+                    b"title" if !is_empty && in_header => in_work_title = true,
+                    b"note" | b"title" if !is_empty => skip_depth += 1,
+                    _ => {},
+                }
+            },
+            Event::End(ref element) => {
+                match element.local_name().as_ref() {
+                    b"verse" => {
+                        if let Some((book, chapter, number)) = verse.take() {
+                            bible.add_verse(book, None, chapter, BibleVerse {
+                                number,
+                                text: collapse_whitespace(&verse_text),
+                            });
+                        }
+                        verse_text.clear();
+                    },
+                    b"header" => in_header = false,
+                    b"title" if in_header => in_work_title = false,
+                    b"note" | b"title" => skip_depth -= 1,
+                    _ => {},
+                }
+            },
+            Event::Text(ref text) => {
+                let text = text.unescape().map_err(|x| format!("Invalid XML: {x}"))?;
+
+                if in_work_title && bible.name == fallback_name {
+                    bible.name = collapse_whitespace(&text);
+                } else if verse.is_some() && skip_depth == 0 {
+                    verse_text.push_str(&text);
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    // A milestone verse that is never closed
+    if let Some((book, chapter, number)) = verse {
+        bible.add_verse(book, None, chapter, BibleVerse {
+            number,
+            text: collapse_whitespace(&verse_text),
+        });
+    }
+
+    if bible.books.is_empty() {
+        return Err("No verses found in OSIS file".to_string());
+    }
+
+    Ok(bible)
+}
+
+/// `John.3.16` to (43, 3, 16). Ranges like `John.3.16 John.3.17` use the first verse.
+fn parse_osis_id(osis_id: &str) -> Option<(u32, u32, u32)> {
+    let first_id = osis_id.split_whitespace().next()?;
+    let mut parts = first_id.split('.');
+
+    let book = book_number_from_osis_id(parts.next()?)?;
+    let chapter = parts.next()?.parse().ok()?;
+    let verse = parts.next()?.parse().ok()?;
+
+    Some((book, chapter, verse))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZEFANIA: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<XMLBIBLE biblename="Test Bible">
+  <BIBLEBOOK bnumber="43" bname="Johannes">
+    <CHAPTER cnumber="3">
+      <CAPTION>Jesus and Nicodemus</CAPTION>
+      <VERS vnumber="16">For God so loved
+        the world<NOTE>Or: so much</NOTE>,</VERS>
+      <VERS vnumber="17">For God sent not his Son</VERS>
+    </CHAPTER>
+  </BIBLEBOOK>
+</XMLBIBLE>"#;
+
+    const OSIS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osis xmlns="http://www.bibletechnologies.net/2003/OSIS/namespace">
+  <osisText osisIDWork="Test">
+    <header><work osisWork="Test"><title>OSIS Test Bible</title></work></header>
+    <div type="book" osisID="Gen">
+      <chapter osisID="Gen.1">
+        <title>The creation</title>
+        <verse osisID="Gen.1.1">In the beginning<note>A note</note></verse>
+        <verse sID="Gen.1.2" osisID="Gen.1.2"/>And the earth<verse eID="Gen.1.2"/>
+        <verse sID="Gen.1.3" osisID="Gen.1.3"/>And God said
+      </chapter>
+    </div>
+  </osisText>
+</osis>"#;
+
+    fn verse_texts(bible: &Bible) -> Vec<(u32, u32, u32, &str)> {
+        bible.books.iter()
+            .flat_map(|book| book.chapters.iter().flat_map(move |chapter| {
+                chapter.verses.iter().map(move |verse| (book.number, chapter.number, verse.number, verse.text.as_str()))
+            }))
+            .collect()
+    }
+
+    #[test]
+    fn import_zefania_reads_verses_without_notes() {
+        let bible = import_zefania(ZEFANIA, "Fallback").unwrap();
+
+        assert_eq!(bible.name, "Test Bible");
+        assert_eq!(bible.books[0].name, "Johannes");
+        assert_eq!(verse_texts(&bible), vec![
+            (43, 3, 16, "For God so loved the world,"),
+            (43, 3, 17, "For God sent not his Son"),
+        ]);
+    }
+
+    #[test]
+    fn import_osis_reads_container_and_milestone_verses() {
+        let bible = import_osis(OSIS, "Fallback").unwrap();
+
+        assert_eq!(bible.name, "OSIS Test Bible");
+        assert_eq!(bible.books[0].name, "Genesis");
+        assert_eq!(verse_texts(&bible), vec![
+            (1, 1, 1, "In the beginning"),
+            (1, 1, 2, "And the earth"),
+            (1, 1, 3, "And God said"),
+        ]);
+    }
+
+    #[test]
+    fn import_bible_xml_detects_the_format() {
+        assert_eq!(import_bible_xml(ZEFANIA, "Fallback").unwrap().name, "Test Bible");
+        assert_eq!(import_bible_xml(OSIS, "Fallback").unwrap().name, "OSIS Test Bible");
+        assert!(import_bible_xml("<html><body/></html>", "Fallback").is_err());
+        assert!(import_bible_xml("", "Fallback").is_err());
+    }
+
+    #[test]
+    fn malformed_or_empty_bibles_are_rejected() {
+        let malformed = r#"<XMLBIBLE><BIBLEBOOK bnumber="1"><CHAPTER cnumber="1"><VERS vnumber="1">text</CHAPTER></XMLBIBLE>"#;
+
+        assert!(import_bible_xml(malformed, "Fallback").is_err());
+        assert!(import_zefania("<XMLBIBLE></XMLBIBLE>", "Fallback").is_err());
+        assert!(import_osis("<osis><osisText/></osis>", "Fallback").is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Book number (as used by Zefania), OSIS id, English name, Dutch name and common abbreviations.
+pub const BOOKS: [(u32, &str, &str, &str, &[&str]); 66] = [
+    (1, "Gen", "Genesis", "Genesis", &["gn"]),
+    (2, "Exod", "Exodus", "Exodus", &["ex"]),
+    (3, "Lev", "Leviticus", "Leviticus", &["lv"]),
+    (4, "Num", "Numbers", "Numeri", &["nm"]),
+    (5, "Deut", "Deuteronomy", "Deuteronomium", &["dt"]),
+    (6, "Josh", "Joshua", "Jozua", &["joz"]),
+    (7, "Judg", "Judges", "Rechters", &["richteren", "re", "ri", "richt"]),
+    (8, "Ruth", "Ruth", "Ruth", &[]),
+    (9, "1Sam", "1 Samuel", "1 Samuel", &[]),
+    (10, "2Sam", "2 Samuel", "2 Samuel", &[]),
+    (11, "1Kgs", "1 Kings", "1 Koningen", &["1kon"]),
+    (12, "2Kgs", "2 Kings", "2 Koningen", &["2kon"]),
+    (13, "1Chr", "1 Chronicles", "1 Kronieken", &["1kron"]),
+    (14, "2Chr", "2 Chronicles", "2 Kronieken", &["2kron"]),
+    (15, "Ezra", "Ezra", "Ezra", &[]),
+    (16, "Neh", "Nehemiah", "Nehemia", &[]),
+    (17, "Esth", "Esther", "Ester", &["est"]),
+    (18, "Job", "Job", "Job", &[]),
+    (19, "Ps", "Psalms", "Psalmen", &["psalm", "psa"]),
+    (20, "Prov", "Proverbs", "Spreuken", &["spr"]),
+    (21, "Eccl", "Ecclesiastes", "Prediker", &["pred", "koh"]),
+    (22, "Song", "Song of Songs", "Hooglied", &["songofsolomon", "hgl", "hl"]),
+    (23, "Isa", "Isaiah", "Jesaja", &["jes"]),
+    (24, "Jer", "Jeremiah", "Jeremia", &[]),
+    (25, "Lam", "Lamentations", "Klaagliederen", &["kla", "klgl"]),
+    (26, "Ezek", "Ezekiel", "Ezechiël", &["ez"]),
+    (27, "Dan", "Daniel", "Daniël", &[]),
+    (28, "Hos", "Hosea", "Hosea", &[]),
+    (29, "Joel", "Joel", "Joël", &[]),
+    (30, "Amos", "Amos", "Amos", &[]),
+    (31, "Obad", "Obadiah", "Obadja", &["ob"]),
+    (32, "Jonah", "Jonah", "Jona", &[]),
+    (33, "Mic", "Micah", "Micha", &[]),
+    (34, "Nah", "Nahum", "Nahum", &[]),
+    (35, "Hab", "Habakkuk", "Habakuk", &[]),
+    (36, "Zeph", "Zephaniah", "Sefanja", &["sef"]),
+    (37, "Hag", "Haggai", "Haggai", &[]),
+    (38, "Zech", "Zechariah", "Zacharia", &["zach"]),
+    (39, "Mal", "Malachi", "Maleachi", &[]),
+    (40, "Matt", "Matthew", "Matteüs", &["mt", "matth", "mattheus"]),
+    (41, "Mark", "Mark", "Marcus", &["mk", "mc", "mr", "markus"]),
+    (42, "Luke", "Luke", "Lucas", &["lk", "luc", "lukas"]),
+    (43, "John", "John", "Johannes", &["jn", "joh"]),
+    (44, "Acts", "Acts", "Handelingen", &["hand"]),
+    (45, "Rom", "Romans", "Romeinen", &[]),
+    (46, "1Cor", "1 Corinthians", "1 Korintiërs", &["1kor", "1korinthiers"]),
+    (47, "2Cor", "2 Corinthians", "2 Korintiërs", &["2kor", "2korinthiers"]),
+    (48, "Gal", "Galatians", "Galaten", &[]),
+    (49, "Eph", "Ephesians", "Efeziërs", &["ef"]),
+    (50, "Phil", "Philippians", "Filippenzen", &["fil", "flp"]),
+    (51, "Col", "Colossians", "Kolossenzen", &["kol"]),
+    (52, "1Thess", "1 Thessalonians", "1 Tessalonicenzen", &["1tes", "1thessalonicenzen"]),
+    (53, "2Thess", "2 Thessalonians", "2 Tessalonicenzen", &["2tes", "2thessalonicenzen"]),
+    (54, "1Tim", "1 Timothy", "1 Timoteüs", &["1timotheus"]),
+    (55, "2Tim", "2 Timothy", "2 Timoteüs", &["2timotheus"]),
+    (56, "Titus", "Titus", "Titus", &["tit"]),
+    (57, "Phlm", "Philemon", "Filemon", &["filem", "flm"]),
+    (58, "Heb", "Hebrews", "Hebreeën", &["hebr"]),
+    (59, "Jas", "James", "Jakobus", &["jak"]),
+    (60, "1Pet", "1 Peter", "1 Petrus", &[]),
+    (61, "2Pet", "2 Peter", "2 Petrus", &[]),
+    (62, "1John", "1 John", "1 Johannes", &["1jn", "1joh"]),
+    (63, "2John", "2 John", "2 Johannes", &["2jn", "2joh"]),
+    (64, "3John", "3 John", "3 Johannes", &["3jn", "3joh"]),
+    (65, "Jude", "Jude", "Judas", &[]),
+    (66, "Rev", "Revelation", "Openbaring", &["op", "openb", "apk"]),
+];
+
+/// A passage within one book, e.g. `John 3:16–4:2`.
+/// Missing verses mean: from the start or up to the end of the chapter.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BibleReference {
+    pub book: u32,
+    pub start_chapter: u32,
+    pub start_verse: Option<u32>,
+    pub end_chapter: u32,
+    pub end_verse: Option<u32>,
+}
+
+impl BibleReference {
+    /// Formats the chapter and verse part of the reference, without the book name.
+    pub fn range_text(&self) -> String {
+        let start = match self.start_verse {
+            Some(verse) => format!("{}:{}", self.start_chapter, verse),
+            None => self.start_chapter.to_string(),
+        };
+
+        let end = match self.end_verse {
+            Some(verse) if self.end_chapter == self.start_chapter && self.start_verse.is_some() => verse.to_string(),
+            Some(verse) => format!("{}:{}", self.end_chapter, verse),
+            None => self.end_chapter.to_string(),
+        };
+
+        if self.start_chapter == self.end_chapter && self.start_verse == self.end_verse {
+            start
+        } else {
+            format!("{start}-{end}")
+        }
+    }
+}
+
+/// Parses references like `Joh 3:16-18`, `John 3:16–4:2`, `1 Kor 13` or `Psalm 23:1`.
+pub fn parse_reference(reference: &str) -> Result<BibleReference, String> {
+    lazy_static! {
+        static ref REFERENCE_REGEX: Regex = Regex::new(
+            r"^\s*(.+?)\s*(\d+)(?:\s*:\s*(\d+))?(?:\s*[-–—]\s*(\d+)(?:\s*:\s*(\d+))?)?\s*$"
+        ).unwrap();
+    }
+
+    let captures = REFERENCE_REGEX.captures(reference)
+        .ok_or(format!("Could not read reference '{reference}'"))?;
+
+    let book = find_book(&captures[1])?;
+
+    let number = |index: usize| captures.get(index).and_then(|x| x.as_str().parse::<u32>().ok());
+    let start_chapter = number(2).ok_or("Invalid chapter".to_string())?;
+    let start_verse = number(3);
+
+    let (end_chapter, end_verse) = match (start_verse, number(4), number(5)) {
+        // `3:16-4:2`
+        (_, Some(chapter), Some(verse)) => (chapter, Some(verse)),
+        // `3:16-18`
+        (Some(_), Some(verse), None) => (start_chapter, Some(verse)),
+        // `3-4`
+        (None, Some(chapter), None) => (chapter, None),
+        // `3:16`
+        (Some(verse), None, _) => (start_chapter, Some(verse)),
+        // `3`
+        (None, None, _) => (start_chapter, None),
+    };
+
+    if (end_chapter, end_verse.unwrap_or(u32::MAX)) < (start_chapter, start_verse.unwrap_or(0)) {
+        return Err(format!("The end of '{reference}' is before its start"));
+    }
+
+    Ok(BibleReference {
+        book,
+        start_chapter,
+        start_verse,
+        end_chapter,
+        end_verse,
+    })
+}
+
+/// Finds the book number for a Dutch or English book name or abbreviation.
+pub fn find_book(name: &str) -> Result<u32, String> {
+    let query = normalize_book_name(name);
+    if query.is_empty() {
+        return Err("No book given".to_string());
+    }
+
+    let candidates = |book: &(u32, &str, &str, &str, &[&str])| {
+        let (_, osis_id, english, dutch, abbreviations) = *book;
+        [osis_id, english, dutch].into_iter()
+            .chain(abbreviations.iter().copied())
+            .map(normalize_book_name)
+            .collect::<Vec<_>>()
+    };
+
+    if let Some(book) = BOOKS.iter().find(|book| candidates(book).contains(&query)) {
+        return Ok(book.0);
+    }
+
+    let mut matching_books = BOOKS.iter()
+        .filter(|book| candidates(book).iter().any(|x| x.starts_with(&query)))
+        .map(|book| book.0);
+
+    match (matching_books.next(), matching_books.next()) {
+        (Some(book), None) => Ok(book),
+        (Some(_), Some(_)) => Err(format!("'{name}' could be multiple books")),
+        (None, _) => Err(format!("Unknown book '{name}'")),
+    }
+}
+
+pub fn book_number_from_osis_id(osis_id: &str) -> Option<u32> {
+    BOOKS.iter().find(|x| x.1.eq_ignore_ascii_case(osis_id)).map(|x| x.0)
+}
+
+pub fn book_english_name(book: u32) -> Option<&'static str> {
+    BOOKS.iter().find(|x| x.0 == book).map(|x| x.2)
+}
+
+/// Lowercase, without spaces, dots and diacritics: `1 Kor.` becomes `1kor`.
+fn normalize_book_name(name: &str) -> String {
+    name.chars()
+        .filter(|x| !x.is_whitespace() && *x != '.')
+        .flat_map(char::to_lowercase)
+        .map(|x| match x {
+            'ë' | 'é' | 'è' => 'e',
+            'ü' => 'u',
+            'ï' => 'i',
+            _ => x,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(book: u32, start: (u32, Option<u32>), end: (u32, Option<u32>)) -> BibleReference {
+        BibleReference {
+            book,
+            start_chapter: start.0,
+            start_verse: start.1,
+            end_chapter: end.0,
+            end_verse: end.1,
+        }
+    }
+
+    #[test]
+    fn parse_reference_reads_verses_and_ranges() {
+        assert_eq!(parse_reference("John 3:16"), Ok(reference(43, (3, Some(16)), (3, Some(16)))));
+        assert_eq!(parse_reference("Joh 3:16-18"), Ok(reference(43, (3, Some(16)), (3, Some(18)))));
+        assert_eq!(parse_reference("John 3:16–4:2"), Ok(reference(43, (3, Some(16)), (4, Some(2)))));
+        assert_eq!(parse_reference("1 Kor 13"), Ok(reference(46, (13, None), (13, None))));
+        assert_eq!(parse_reference("Psalm 23 - 24"), Ok(reference(19, (23, None), (24, None))));
+        assert_eq!(parse_reference("  psalm 23 : 1 "), Ok(reference(19, (23, Some(1)), (23, Some(1)))));
+    }
+
+    #[test]
+    fn parse_reference_rejects_invalid_references() {
+        assert!(parse_reference("John").is_err());
+        assert!(parse_reference("Nobook 3:16").is_err());
+        assert!(parse_reference("John 3:16-14").is_err());
+        assert!(parse_reference("John 4-3").is_err());
+    }
+
+    #[test]
+    fn range_text_leaves_out_what_is_implied() {
+        assert_eq!(reference(43, (3, Some(16)), (3, Some(16))).range_text(), "3:16");
+        assert_eq!(reference(43, (3, Some(16)), (3, Some(18))).range_text(), "3:16-18");
+        assert_eq!(reference(43, (3, Some(16)), (4, Some(2))).range_text(), "3:16-4:2");
+        assert_eq!(reference(43, (3, None), (4, None)).range_text(), "3-4");
+    }
+
+    #[test]
+    fn find_book_accepts_english_and_dutch_names_and_abbreviations() {
+        assert_eq!(find_book("Genesis"), Ok(1));
+        assert_eq!(find_book("Matteüs"), Ok(40));
+        assert_eq!(find_book("Mattheus"), Ok(40));
+        assert_eq!(find_book("1 Kor."), Ok(46));
+        assert_eq!(find_book("ezechiel"), Ok(26));
+        assert_eq!(find_book("1Thess"), Ok(52));
+    }
+
+    #[test]
+    fn find_book_accepts_unique_prefixes() {
+        assert_eq!(find_book("Openb"), Ok(66));
+        assert_eq!(find_book("Deuter"), Ok(5));
+    }
+
+    #[test]
+    fn find_book_rejects_unknown_and_ambiguous_names() {
+        assert_eq!(find_book("Jo"), Err("'Jo' could be multiple books".to_string()));
+        assert_eq!(find_book("Hezekiah"), Err("Unknown book 'Hezekiah'".to_string()));
+        assert!(find_book(" . ").is_err());
+    }
+}
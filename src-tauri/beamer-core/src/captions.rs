@@ -0,0 +1,225 @@
+use std::time::Instant;
+use serde::Serialize;
+
+/// A verse that was live from `start_ms` until `end_ms`, relative to the recording start.
+#[derive(Clone, Debug, Serialize)]
+pub struct CaptionCue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub lines: Vec<String>,
+}
+
+/// Follows what is live on the presentation, and logs it as cues while recording.
+#[derive(Clone, Debug, Default)]
+pub struct CaptionLog {
+    recording_started: Option<Instant>,
+    recording: bool,
+    cues: Vec<CaptionCue>,
+    /// Start and lines of the cue that is live right now.
+    open_cue: Option<(u64, Vec<String>)>,
+    verse_lines: Vec<String>,
+    blanked: bool,
+}
+
+impl CaptionLog {
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    pub fn cues(&self) -> &[CaptionCue] {
+        &self.cues
+    }
+
+    /// Starts a new log, with the current moment as time zero.
+    pub fn start_recording(&mut self, verse_lines: Vec<String>, blanked: bool) {
+        self.recording_started = Some(Instant::now());
+        self.recording = true;
+        self.cues.clear();
+        self.open_cue = None;
+        self.verse_lines = verse_lines;
+        self.blanked = blanked;
+
+        self.update();
+    }
+
+    pub fn stop_recording(&mut self) -> Option<CaptionCue> {
+        let closed_cue = self.close_cue();
+        self.recording = false;
+
+        closed_cue
+    }
+
+    pub fn set_verse(&mut self, verse_lines: Vec<String>) -> Option<CaptionCue> {
+        self.verse_lines = verse_lines;
+        self.update()
+    }
+
+    pub fn set_blanked(&mut self, blanked: bool) -> Option<CaptionCue> {
+        self.blanked = blanked;
+        self.update()
+    }
+
+    /// Closes the open cue when the visible text changed, and opens the next one.
+    /// Returns the closed cue.
+    fn update(&mut self) -> Option<CaptionCue> {
+        if !self.recording {
+            return None;
+        }
+
+        let visible_lines = self.visible_lines();
+        if self.open_cue.as_ref().map(|(_, lines)| lines) == visible_lines.as_ref() {
+            return None;
+        }
+
+        let closed_cue = self.close_cue();
+        self.open_cue = visible_lines.map(|lines| (self.elapsed_ms(), lines));
+
+        closed_cue
+    }
+
+    fn close_cue(&mut self) -> Option<CaptionCue> {
+        let (start_ms, lines) = self.open_cue.take()?;
+        let cue = CaptionCue {
+            start_ms,
+            end_ms: self.elapsed_ms(),
+            lines,
+        };
+        self.cues.push(cue.clone());
+
+        Some(cue)
+    }
+
+    /// `None` when nothing readable is on screen.
+    fn visible_lines(&self) -> Option<Vec<String>> {
+        let lines = self.verse_lines.iter()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+
+        if self.blanked || lines.is_empty() {
+            None
+        } else {
+            Some(lines)
+        }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.recording_started
+            .map(|x| x.elapsed().as_millis() as u64)
+            .unwrap_or(0)
+    }
+}
+
+pub fn to_srt(cues: &[CaptionCue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(index, cue)| format!(
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            timestamp(cue.start_ms, ','),
+            timestamp(cue.end_ms, ','),
+            cue.lines.join("\n"),
+        ))
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+pub fn to_webvtt(cues: &[CaptionCue]) -> String {
+    let mut webvtt = "WEBVTT\n\n".to_string();
+    webvtt.extend(cues.iter().map(webvtt_cue));
+
+    webvtt
+}
+
+/// One cue, for the live WebVTT stream.
+pub fn webvtt_cue(cue: &CaptionCue) -> String {
+    format!(
+        "{} --> {}\n{}\n\n",
+        timestamp(cue.start_ms, '.'),
+        timestamp(cue.end_ms, '.'),
+        // Cue text is markup: `<` would start a tag, `-->` is not allowed
+        cue.lines.join("\n").replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;"),
+    )
+}
+
+/// `01:02:03,456` for SRT, `01:02:03.456` for WebVTT.
+fn timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start_ms: u64, end_ms: u64, lines: &[&str]) -> CaptionCue {
+        CaptionCue {
+            start_ms,
+            end_ms,
+            lines: lines.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn timestamp_has_hours_minutes_seconds_and_milliseconds() {
+        assert_eq!(timestamp(0, ','), "00:00:00,000");
+        assert_eq!(timestamp(3_723_456, ','), "01:02:03,456");
+        assert_eq!(timestamp(59_999, '.'), "00:00:59.999");
+        // Past a day the hours keep counting
+        assert_eq!(timestamp(100 * 3_600_000, '.'), "100:00:00.000");
+    }
+
+    #[test]
+    fn to_srt_numbers_the_cues() {
+        let cues = [cue(1_500, 4_000, &["Amazing grace", "How sweet the sound"]), cue(4_000, 65_010, &["That saved"])];
+
+        assert_eq!(to_srt(&cues), "\
+1
+00:00:01,500 --> 00:00:04,000
+Amazing grace
+How sweet the sound
+
+2
+00:00:04,000 --> 00:01:05,010
+That saved
+
+");
+    }
+
+    #[test]
+    fn to_webvtt_has_a_header_and_escapes_markup() {
+        let cues = [cue(0, 2_000, &["Rock & <roll> -->"])];
+
+        assert_eq!(to_webvtt(&cues), "\
+WEBVTT
+
+00:00:00.000 --> 00:00:02.000
+Rock &amp; &lt;roll&gt; --&gt;
+
+");
+        assert_eq!(to_webvtt(&[]), "WEBVTT\n\n");
+    }
+
+    #[test]
+    fn caption_log_only_logs_visible_text_while_recording() {
+        let mut captions = CaptionLog::default();
+        assert_eq!(captions.set_verse(vec!["Before".to_string()]).map(|x| x.lines), None);
+
+        captions.start_recording(vec!["First".to_string()], false);
+        let closed_cue = captions.set_blanked(true).unwrap();
+        assert_eq!(closed_cue.lines, vec!["First"]);
+
+        // Nothing visible while blanked
+        assert!(captions.set_verse(vec!["Second".to_string()]).is_none());
+        assert!(captions.set_blanked(false).is_none());
+        assert_eq!(captions.stop_recording().unwrap().lines, vec!["Second"]);
+        assert_eq!(captions.cues().len(), 2);
+    }
+}
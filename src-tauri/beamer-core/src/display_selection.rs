@@ -0,0 +1,496 @@
+use std::cmp::{max, min};
+use std::fmt;
+use serde::Serialize;
+use crate::song::{Countdown, CustomSlide, ImageSlide, SongList, SongSlot, SongSlotType, Verse};
+
+/// For tracking which verse to currently show. Refers to the slot in the song list by id,
+/// so it stays small and never goes out of date when a slot is edited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplaySelection {
+    slot_id: u64,
+    /// Where the slot was, used when the slot was removed.
+    slot_position: usize,
+    verse_num: usize,
+}
+
+/// What the main window and remote clients show of a selection: only the selected verse of the slot.
+#[derive(Clone, Debug, Serialize)]
+pub struct SelectionView {
+    pub slot_id: u64,
+    pub slot_position: usize,
+    pub verse_num: usize,
+    pub num_verses: usize,
+    pub kind: SlotKind,
+    pub title: String,
+    pub author: String,
+    pub verse: Verse,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum SlotKind {
+    Empty,
+    Song,
+    Image,
+    CustomSlides,
+    Bible,
+    Countdown,
+}
+
+/// Sent as `update-display-selection`.
+#[derive(Clone, Debug, Serialize)]
+pub struct SelectionUpdate {
+    /// Goes up by one with every update, so a client can tell it missed one.
+    pub version: u64,
+    pub current: SelectionView,
+    pub next: SelectionView,
+}
+
+/// Why navigating failed. The selection stays where it was.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NavigationError {
+    SlotNotFound { slot_id: u64 },
+    PositionOutOfRange { position: usize, num_slots: usize },
+    VerseOutOfRange { slot_id: u64, verse_num: usize, num_verses: usize },
+}
+
+impl fmt::Display for NavigationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SlotNotFound { slot_id } => write!(f, "Slot {slot_id} not found"),
+            Self::PositionOutOfRange { position, num_slots } => write!(f, "No slot at position {position}, the song list has {num_slots} slots"),
+            Self::VerseOutOfRange { slot_id, verse_num, num_verses } => write!(f, "Verse {verse_num} not found, slot {slot_id} has {num_verses} verses"),
+        }
+    }
+}
+
+impl std::error::Error for NavigationError {}
+
+impl From<NavigationError> for String {
+    fn from(error: NavigationError) -> Self {
+        error.to_string()
+    }
+}
+
+impl DisplaySelection {
+    pub fn new(song_list: &SongList, song_position: usize, verse_num: Option<usize>) -> Result<Self, NavigationError> {
+        let verse_num = verse_num.unwrap_or(0);
+
+        let song_slot = song_list.songs.get(song_position).ok_or(NavigationError::PositionOutOfRange {
+            position: song_position,
+            num_slots: song_list.songs.len(),
+        })?;
+
+        let num_verses = verse_count(&song_slot.slot);
+        if verse_num >= num_verses {
+            return Err(NavigationError::VerseOutOfRange {
+                slot_id: song_slot.id,
+                verse_num,
+                num_verses,
+            });
+        }
+
+        Ok(Self {
+            slot_id: song_slot.id,
+            slot_position: song_position,
+            verse_num,
+        })
+    }
+
+    pub fn for_slot(song_list: &SongList, slot_id: u64, verse_num: Option<usize>) -> Result<Self, NavigationError> {
+        let position = song_list.songs.iter()
+            .position(|x| x.id == slot_id)
+            .ok_or(NavigationError::SlotNotFound { slot_id })?;
+
+        Self::new(song_list, position, verse_num)
+    }
+
+    /// For an empty song list.
+    pub fn empty() -> Self {
+        Self {
+            slot_id: 0,
+            slot_position: 0,
+            verse_num: 0,
+        }
+    }
+
+    /// Shown as an empty slot when the slot was removed.
+    fn current_slot<'a>(&self, song_list: &'a SongList) -> Option<&'a SongSlot> {
+        song_list.songs.iter().find(|x| x.id == self.slot_id)
+    }
+
+    fn current_slot_type<'a>(&self, song_list: &'a SongList) -> &'a SongSlotType {
+        self.current_slot(song_list).map(|x| &x.slot).unwrap_or(&SongSlotType::Empty)
+    }
+
+    pub fn current_verse(&self, song_list: &SongList) -> Verse {
+        slot_verse(self.current_slot_type(song_list), self.verse_num)
+    }
+
+    pub fn current_image<'a>(&self, song_list: &'a SongList) -> Option<&'a ImageSlide> {
+        match self.current_slot_type(song_list) {
+            SongSlotType::Image(ref image) => Some(image),
+            _ => None,
+        }
+    }
+
+    pub fn current_custom_slide<'a>(&self, song_list: &'a SongList) -> Option<&'a CustomSlide> {
+        match self.current_slot_type(song_list) {
+            SongSlotType::CustomSlides(ref custom_slides) => custom_slides.slides.get(self.verse_num),
+            _ => None,
+        }
+    }
+
+    pub fn current_countdown<'a>(&self, song_list: &'a SongList) -> Option<&'a Countdown> {
+        match self.current_slot_type(song_list) {
+            SongSlotType::Countdown(ref countdown) => Some(countdown),
+            _ => None,
+        }
+    }
+
+    pub fn slot_id(&self) -> u64 {
+        self.slot_id
+    }
+
+    pub fn slot_position(&self) -> usize {
+        self.slot_position
+    }
+
+    pub fn verse_num(&self) -> usize {
+        self.verse_num
+    }
+
+    pub fn song_title<'a>(&self, song_list: &'a SongList) -> &'a str {
+        title_and_author(self.current_slot_type(song_list)).0
+    }
+
+    /// Where in the slot the selection is, e.g. `Verse 2 of 4`.
+    pub fn section_label(&self, song_list: &SongList) -> String {
+        let slot = self.current_slot_type(song_list);
+        let total = verse_count(slot);
+
+        match slot {
+            SongSlotType::Empty => "Empty".to_string(),
+            SongSlotType::Song(_) => format!("Verse {} of {}", self.verse_num + 1, total),
+            SongSlotType::Image(_) => "Image".to_string(),
+            SongSlotType::CustomSlides(_) | SongSlotType::Bible(_) => format!("Slide {} of {}", self.verse_num + 1, total),
+            SongSlotType::Countdown(_) => "Countdown".to_string(),
+        }
+    }
+
+    pub fn view(&self, song_list: &SongList) -> SelectionView {
+        let slot = self.current_slot_type(song_list);
+        let (title, author) = title_and_author(slot);
+
+        SelectionView {
+            slot_id: self.slot_id,
+            slot_position: self.slot_position,
+            verse_num: self.verse_num,
+            num_verses: verse_count(slot),
+            kind: slot_kind(slot),
+            title: title.to_string(),
+            author: author.to_string(),
+            verse: slot_verse(slot, self.verse_num),
+        }
+    }
+
+    /// Keeps the selection on its slot after the song list changed: the slot may have moved,
+    /// or its content may have been replaced. If the slot was removed, the slot that took its place is selected.
+    /// Returns whether the shown content changed.
+    pub fn follow_slot(&mut self, previous_song_list: &SongList, song_list: &SongList) -> bool {
+        let position = match song_list.songs.iter().position(|x| x.id == self.slot_id) {
+            Some(position) => position,
+            None => {
+                let position = min(self.slot_position, song_list.songs.len().saturating_sub(1));
+                *self = Self::new(song_list, position, None).unwrap_or_else(|_| Self::empty());
+                return true;
+            },
+        };
+        self.slot_position = position;
+
+        let slot = &song_list.songs[position].slot;
+        if self.current_slot(previous_song_list).map(|x| &x.slot) == Some(slot) {
+            return false;
+        }
+
+        self.verse_num = min(self.verse_num, verse_count(slot) - 1);
+        true
+    }
+
+    pub fn is_same_slide(&self, other: &DisplaySelection) -> bool {
+        self.slot_id == other.slot_id && self.verse_num == other.verse_num
+    }
+
+    fn position_in(&self, song_list: &SongList) -> Option<usize> {
+        song_list.songs.iter().position(|x| x.id == self.slot_id)
+    }
+
+    fn at(song_list: &SongList, position: usize, verse_num: usize) -> Self {
+        Self {
+            slot_id: song_list.songs[position].id,
+            slot_position: position,
+            verse_num,
+        }
+    }
+
+    pub fn previous(&mut self, song_list: &SongList) {
+        let current_position = self.position_in(song_list);
+
+        if current_position.is_some() && self.verse_num > 0 {
+            self.verse_num -= 1;
+            return;
+        }
+
+        // saturating sub: prevent underflow
+        let new_position = current_position.map(|x| x.saturating_sub(1)).unwrap_or(self.slot_position);
+
+        if new_position < song_list.songs.len() {
+            let verse_num = verse_count(&song_list.songs[new_position].slot) - 1;
+            *self = Self::at(song_list, new_position, verse_num);
+        } else if !song_list.songs.is_empty() {
+            *self = Self::at(song_list, song_list.songs.len() - 1, 0);
+        } else {
+            *self = Self::empty();
+        }
+    }
+
+    pub fn next(&mut self, song_list: &SongList) {
+        let current_position = self.position_in(song_list);
+
+        if let Some(position) = current_position {
+            if self.verse_num + 1 < verse_count(&song_list.songs[position].slot) {
+                // Next verse in same song available: Go there
+                self.verse_num += 1;
+                return;
+            }
+        }
+
+        // Go to next song
+        // If song removed: Go to song that is now at that position
+        let new_position = current_position.map(|x| x + 1).unwrap_or(self.slot_position);
+
+        if new_position < song_list.songs.len() {
+            // Go to next if not at end
+            *self = Self::at(song_list, new_position, 0);
+        } else if let Some(last_slot) = song_list.songs.last() {
+            // Or if index more than songlist length: Go to last song
+            *self = Self::at(song_list, song_list.songs.len() - 1, verse_count(&last_slot.slot) - 1);
+        } else {
+            // If Songlist empty: Go to empty song slot with id 0
+            *self = Self::empty();
+        }
+    }
+
+    /// Like `next`, but wraps from the end of the last slot back to the start of the first slot.
+    /// Outside the range, it also jumps to the first slot.
+    pub fn next_in_range(&mut self, song_list: &SongList, first_slot_id: u64, last_slot_id: u64) {
+        let position = |slot_id: u64| song_list.songs.iter().position(|x| x.id == slot_id);
+
+        let (first_position, last_position) = match (position(first_slot_id), position(last_slot_id)) {
+            (Some(first), Some(last)) if first <= last => (first, last),
+            // Range removed from the song list
+            _ => return self.next(song_list),
+        };
+
+        let at_end_of_range = match position(self.slot_id) {
+            Some(current) => {
+                current < first_position
+                    || current > last_position
+                    || (current == last_position && self.verse_num + 1 >= verse_count(&song_list.songs[current].slot))
+            },
+            None => true,
+        };
+
+        if at_end_of_range {
+            *self = Self::at(song_list, first_position, 0);
+        } else {
+            self.next(song_list);
+        }
+    }
+}
+
+/// Slots without text still show one, empty, verse.
+fn verse_count(slot: &SongSlotType) -> usize {
+    max(slot.num_verses(), 1)
+}
+
+fn slot_verse(slot: &SongSlotType, verse_num: usize) -> Verse {
+    let verse = match slot {
+        SongSlotType::Song(song) => song.verses.get(verse_num).cloned(),
+        SongSlotType::Bible(passage) => passage.slides.get(verse_num).cloned(),
+        SongSlotType::CustomSlides(custom_slides) => custom_slides.slides.get(verse_num).map(CustomSlide::to_verse),
+        SongSlotType::Empty | SongSlotType::Image(_) | SongSlotType::Countdown(_) => None,
+    };
+
+    verse.unwrap_or_default()
+}
+
+fn title_and_author(slot: &SongSlotType) -> (&str, &str) {
+    match slot {
+        SongSlotType::Empty => ("Empty Panel", "None"),
+        SongSlotType::Song(song) => (&song.title, &song.author),
+        SongSlotType::Image(image) => (&image.title, "Image"),
+        SongSlotType::CustomSlides(custom_slides) => (&custom_slides.title, "Custom Slides"),
+        SongSlotType::Bible(passage) => (&passage.reference, &passage.bible_name),
+        SongSlotType::Countdown(countdown) => (&countdown.label, "Countdown"),
+    }
+}
+
+fn slot_kind(slot: &SongSlotType) -> SlotKind {
+    match slot {
+        SongSlotType::Empty => SlotKind::Empty,
+        SongSlotType::Song(_) => SlotKind::Song,
+        SongSlotType::Image(_) => SlotKind::Image,
+        SongSlotType::CustomSlides(_) => SlotKind::CustomSlides,
+        SongSlotType::Bible(_) => SlotKind::Bible,
+        SongSlotType::Countdown(_) => SlotKind::Countdown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{song_list, song_slot};
+
+    /// Slot and verse, to compare selections in one assert.
+    fn at(selection: &DisplaySelection) -> (u64, usize) {
+        (selection.slot_id(), selection.verse_num())
+    }
+
+    #[test]
+    fn next_goes_through_the_verses_then_to_the_next_slot() {
+        let song_list = song_list(vec![song_slot("A", 2), song_slot("B", 1)]);
+        let mut selection = DisplaySelection::new(&song_list, 0, None).unwrap();
+
+        selection.next(&song_list);
+        assert_eq!(at(&selection), (0, 1));
+        selection.next(&song_list);
+        assert_eq!(at(&selection), (1, 0));
+    }
+
+    #[test]
+    fn next_stays_on_the_last_verse() {
+        let song_list = song_list(vec![song_slot("A", 1), song_slot("B", 2)]);
+        let mut selection = DisplaySelection::new(&song_list, 1, Some(1)).unwrap();
+
+        selection.next(&song_list);
+        assert_eq!(at(&selection), (1, 1));
+    }
+
+    #[test]
+    fn next_after_a_removed_slot_goes_to_the_slot_in_its_place() {
+        let mut song_list = song_list(vec![song_slot("A", 1), song_slot("B", 1), song_slot("C", 1)]);
+        let mut selection = DisplaySelection::new(&song_list, 1, None).unwrap();
+        song_list.songs.remove(1);
+
+        selection.next(&song_list);
+        assert_eq!(at(&selection), (2, 0));
+    }
+
+    #[test]
+    fn next_in_an_empty_song_list_is_empty() {
+        let song_list = song_list(vec![]);
+        let mut selection = DisplaySelection::empty();
+
+        selection.next(&song_list);
+        assert_eq!(selection, DisplaySelection::empty());
+    }
+
+    #[test]
+    fn previous_goes_to_the_last_verse_of_the_previous_slot() {
+        let song_list = song_list(vec![song_slot("A", 3), song_slot("B", 2)]);
+        let mut selection = DisplaySelection::new(&song_list, 1, Some(1)).unwrap();
+
+        selection.previous(&song_list);
+        assert_eq!(at(&selection), (1, 0));
+        selection.previous(&song_list);
+        assert_eq!(at(&selection), (0, 2));
+    }
+
+    #[test]
+    fn new_rejects_positions_and_verses_out_of_range() {
+        let song_list = song_list(vec![song_slot("A", 2)]);
+
+        assert_eq!(
+            DisplaySelection::new(&song_list, 1, None),
+            Err(NavigationError::PositionOutOfRange { position: 1, num_slots: 1 }),
+        );
+        assert_eq!(
+            DisplaySelection::new(&song_list, 0, Some(2)),
+            Err(NavigationError::VerseOutOfRange { slot_id: 0, verse_num: 2, num_verses: 2 }),
+        );
+        assert_eq!(DisplaySelection::for_slot(&song_list, 5, None), Err(NavigationError::SlotNotFound { slot_id: 5 }));
+    }
+
+    #[test]
+    fn follow_slot_keeps_a_moved_slot_selected() {
+        let previous_song_list = song_list(vec![song_slot("A", 1), song_slot("B", 2)]);
+        let mut selection = DisplaySelection::new(&previous_song_list, 1, Some(1)).unwrap();
+        let mut song_list = previous_song_list.clone();
+        song_list.songs.swap(0, 1);
+
+        assert!(!selection.follow_slot(&previous_song_list, &song_list));
+        assert_eq!(at(&selection), (1, 1));
+        assert_eq!(selection.slot_position(), 0);
+    }
+
+    #[test]
+    fn follow_slot_clamps_the_verse_of_a_replaced_slot() {
+        let previous_song_list = song_list(vec![song_slot("A", 3)]);
+        let mut selection = DisplaySelection::new(&previous_song_list, 0, Some(2)).unwrap();
+        let song_list = song_list(vec![song_slot("A2", 2)]);
+
+        assert!(selection.follow_slot(&previous_song_list, &song_list));
+        assert_eq!(at(&selection), (0, 1));
+    }
+
+    #[test]
+    fn follow_slot_selects_the_slot_in_place_of_a_removed_slot() {
+        let previous_song_list = song_list(vec![song_slot("A", 1), song_slot("B", 2), song_slot("C", 1)]);
+        let mut selection = DisplaySelection::new(&previous_song_list, 1, Some(1)).unwrap();
+        let mut song_list = previous_song_list.clone();
+        song_list.songs.remove(1);
+
+        assert!(selection.follow_slot(&previous_song_list, &song_list));
+        assert_eq!(at(&selection), (2, 0));
+    }
+
+    #[test]
+    fn follow_slot_selects_the_last_slot_when_the_last_slot_was_removed() {
+        let previous_song_list = song_list(vec![song_slot("A", 1), song_slot("B", 1)]);
+        let mut selection = DisplaySelection::new(&previous_song_list, 1, None).unwrap();
+        let mut song_list = previous_song_list.clone();
+        song_list.songs.pop();
+
+        assert!(selection.follow_slot(&previous_song_list, &song_list));
+        assert_eq!(at(&selection), (0, 0));
+    }
+
+    #[test]
+    fn next_in_range_wraps_to_the_first_slot() {
+        let song_list = song_list(vec![song_slot("A", 1), song_slot("B", 1), song_slot("C", 2), song_slot("D", 1)]);
+        let mut selection = DisplaySelection::new(&song_list, 2, Some(1)).unwrap();
+
+        selection.next_in_range(&song_list, 1, 2);
+        assert_eq!(at(&selection), (1, 0));
+        selection.next_in_range(&song_list, 1, 2);
+        assert_eq!(at(&selection), (2, 0));
+    }
+
+    #[test]
+    fn next_in_range_jumps_into_the_range() {
+        let song_list = song_list(vec![song_slot("A", 1), song_slot("B", 1), song_slot("C", 1)]);
+        let mut selection = DisplaySelection::new(&song_list, 0, None).unwrap();
+
+        selection.next_in_range(&song_list, 1, 2);
+        assert_eq!(at(&selection), (1, 0));
+    }
+
+    #[test]
+    fn next_in_range_without_the_range_is_next() {
+        let song_list = song_list(vec![song_slot("A", 1), song_slot("B", 1)]);
+        let mut selection = DisplaySelection::new(&song_list, 0, None).unwrap();
+
+        selection.next_in_range(&song_list, 7, 8);
+        assert_eq!(at(&selection), (1, 0));
+    }
+}
@@ -0,0 +1,24 @@
+use crate::display_selection::{DisplaySelection, SelectionUpdate};
+use crate::history::HistoryLabels;
+use crate::setlist::SongListUpdate;
+use crate::song::SongList;
+
+/// Where a `Session` sends its changes, e.g. to the windows of the app.
+/// Called while the session is being changed, so implementations should only pass the events on.
+pub trait EventSink {
+    /// Sent before the selection update for the same change.
+    fn song_list_changed(&self, _update: &SongListUpdate) {}
+
+    fn history_changed(&self, _labels: &HistoryLabels) {}
+
+    /// The current or next selection, sent after every change to the song list or the selection.
+    fn selection_changed(&self, _update: &SelectionUpdate) {}
+
+    /// Something else should be shown, e.g. the next verse or the slot that replaced the live slot.
+    fn live_slide_changed(&self, _song_list: &SongList, _selection: &DisplaySelection) {}
+}
+
+/// Drops all events, for when nothing is listening.
+pub struct NoEvents;
+
+impl EventSink for NoEvents {}
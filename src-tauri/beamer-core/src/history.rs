@@ -0,0 +1,177 @@
+use serde::Serialize;
+use crate::song::{SongList, SongSlot};
+
+/// Older changes are forgotten.
+const MAX_HISTORY: usize = 100;
+
+/// A change to the song list, with the list as it was before.
+#[derive(Clone, Debug)]
+struct HistoryEntry {
+    label: String,
+    song_list: SongList,
+}
+
+/// Undo and redo of song list changes, newest last.
+#[derive(Clone, Debug, Default)]
+pub struct SongListHistory {
+    undo_stack: Vec<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+}
+
+/// What undo and redo would do, for the buttons in the main window.
+#[derive(Clone, Debug, Serialize)]
+pub struct HistoryLabels {
+    pub undo: Option<String>,
+    pub redo: Option<String>,
+}
+
+impl SongListHistory {
+    /// Call with the song list from before the change.
+    pub fn record(&mut self, label: String, previous_song_list: SongList) {
+        self.undo_stack.push(HistoryEntry {
+            label,
+            song_list: previous_song_list,
+        });
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Returns the song list to go back to, and keeps `current_song_list` for redo.
+    pub fn undo(&mut self, current_song_list: SongList) -> Option<SongList> {
+        let entry = self.undo_stack.pop()?;
+        self.redo_stack.push(HistoryEntry {
+            label: entry.label,
+            song_list: current_song_list,
+        });
+
+        Some(entry.song_list)
+    }
+
+    pub fn redo(&mut self, current_song_list: SongList) -> Option<SongList> {
+        let entry = self.redo_stack.pop()?;
+        self.undo_stack.push(HistoryEntry {
+            label: entry.label,
+            song_list: current_song_list,
+        });
+
+        Some(entry.song_list)
+    }
+
+    pub fn labels(&self) -> HistoryLabels {
+        HistoryLabels {
+            undo: self.undo_stack.last().map(|x| format!("Undo: {}", x.label)),
+            redo: self.redo_stack.last().map(|x| format!("Redo: {}", x.label)),
+        }
+    }
+}
+
+/// Describes a change from the frontend, which sends the whole list, e.g. `Removed 'Amazing Grace'`.
+/// Returns `None` when nothing changed.
+pub fn describe_change(previous: &SongList, next: &SongList) -> Option<String> {
+    let previous_ids = previous.songs.iter().map(|x| x.id).collect::<Vec<_>>();
+    let next_ids = next.songs.iter().map(|x| x.id).collect::<Vec<_>>();
+
+    let removed = previous.songs.iter().filter(|x| !next_ids.contains(&x.id)).collect::<Vec<_>>();
+    let added = next.songs.iter().filter(|x| !previous_ids.contains(&x.id)).collect::<Vec<_>>();
+
+    match (removed.as_slice(), added.as_slice()) {
+        ([], []) => {},
+        ([removed], []) => return Some(format!("Removed '{}'", removed.slot.title())),
+        ([], [added]) => return Some(format!("Added '{}'", added.slot.title())),
+        _ => return Some("Changed the song list".to_string()),
+    }
+
+    if previous_ids != next_ids {
+        return Some(match moved_slot(&previous.songs, &next_ids) {
+            Some(moved) => format!("Moved '{}'", moved.slot.title()),
+            None => "Reordered the song list".to_string(),
+        });
+    }
+
+    let mut edited = previous.songs.iter()
+        .zip(next.songs.iter())
+        .filter(|(previous, next)| previous.slot != next.slot);
+
+    match (edited.next(), edited.next()) {
+        (None, _) => None,
+        (Some((_, edited)), None) => Some(format!("Edited '{}'", edited.slot.title())),
+        (Some(_), Some(_)) => Some("Edited the song list".to_string()),
+    }
+}
+
+/// The single slot that was dragged to another position, if that explains the new order.
+fn moved_slot<'a>(previous: &'a [SongSlot], next_ids: &[u64]) -> Option<&'a SongSlot> {
+    previous.iter().find(|slot| {
+        let without = |ids: &mut dyn Iterator<Item = u64>| ids.filter(|x| *x != slot.id).collect::<Vec<_>>();
+        without(&mut previous.iter().map(|x| x.id)) == without(&mut next_ids.iter().copied())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{song_list, song_slot};
+
+    #[test]
+    fn undo_and_redo_restore_the_song_lists() {
+        let first = song_list(vec![song_slot("A", 1)]);
+        let second = song_list(vec![song_slot("A", 1), song_slot("B", 1)]);
+        let mut history = SongListHistory::default();
+        history.record("Added 'B'".to_string(), first.clone());
+
+        assert_eq!(history.undo(second.clone()), Some(first.clone()));
+        assert_eq!(history.labels().redo.as_deref(), Some("Redo: Added 'B'"));
+        assert_eq!(history.undo(first.clone()), None);
+
+        assert_eq!(history.redo(first), Some(second));
+        assert_eq!(history.labels().undo.as_deref(), Some("Undo: Added 'B'"));
+        assert_eq!(history.labels().redo, None);
+    }
+
+    #[test]
+    fn a_new_change_clears_redo() {
+        let first = song_list(vec![]);
+        let mut history = SongListHistory::default();
+        history.record("Added 'A'".to_string(), first.clone());
+        history.undo(song_list(vec![song_slot("A", 1)]));
+
+        history.record("Added 'B'".to_string(), first.clone());
+        assert_eq!(history.redo(first), None);
+    }
+
+    #[test]
+    fn old_changes_are_forgotten() {
+        let mut history = SongListHistory::default();
+        for _ in 0..MAX_HISTORY + 1 {
+            history.record("Edited".to_string(), song_list(vec![]));
+        }
+
+        let mut undo_count = 0;
+        while history.undo(song_list(vec![])).is_some() {
+            undo_count += 1;
+        }
+        assert_eq!(undo_count, MAX_HISTORY);
+    }
+
+    #[test]
+    fn describe_change_names_the_changed_slot() {
+        let previous = song_list(vec![song_slot("A", 1), song_slot("B", 1), song_slot("C", 1)]);
+
+        let mut removed = previous.clone();
+        removed.songs.remove(1);
+        assert_eq!(describe_change(&previous, &removed).as_deref(), Some("Removed 'B'"));
+
+        let mut moved = previous.clone();
+        let slot = moved.songs.remove(0);
+        moved.songs.push(slot);
+        assert_eq!(describe_change(&previous, &moved).as_deref(), Some("Moved 'A'"));
+
+        let mut edited = previous.clone();
+        edited.songs[2].slot = song_slot("C", 2);
+        assert_eq!(describe_change(&previous, &edited).as_deref(), Some("Edited 'C'"));
+
+        assert_eq!(describe_change(&previous, &previous), None);
+    }
+}
@@ -0,0 +1,21 @@
+//! Everything Beamer does that does not need a window: the song list and navigating it,
+//! parsing songs and bibles, caption files, the song library and loading songs from Genius.
+//! The app, and anything else that shows a `Session`, only passes on its events.
+
+pub mod song;
+pub mod song_import;
+pub mod library;
+pub mod display_selection;
+pub mod history;
+pub mod setlist;
+pub mod session;
+pub mod events;
+pub mod querying;
+pub mod bible;
+pub mod bible_import;
+pub mod bible_reference;
+pub mod captions;
+/// Shared by the song and bible importers.
+mod xml;
+#[cfg(test)]
+mod test_util;
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::song::Song;
+
+/// The folder of the library in the app data dir.
+pub const LIBRARY_DIR: &str = "songs";
+
+/// Songs kept for later services, stored as one JSON file per song.
+pub struct Library {
+    dir: PathBuf,
+}
+
+impl Library {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// All songs, sorted by title.
+    pub fn songs(&self) -> Result<Vec<Song>, String> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            // Nothing added yet
+            Err(_) => return Ok(vec![]),
+        };
+
+        let mut songs = entries
+            .filter_map(|x| x.ok())
+            .map(|x| x.path())
+            .filter(|x| x.extension().map(|extension| extension == "json").unwrap_or(false))
+            .map(|x| read_song(&x))
+            .collect::<Result<Vec<_>, _>>()?;
+        songs.sort_by_key(|x| x.title.to_lowercase());
+
+        Ok(songs)
+    }
+
+    /// Songs with `query` in the title, author or lyrics, ignoring case.
+    pub fn search(&self, query: &str) -> Result<Vec<Song>, String> {
+        let query = query.to_lowercase();
+
+        Ok(self.songs()?
+            .into_iter()
+            .filter(|song| {
+                song.title.to_lowercase().contains(&query)
+                    || song.author.to_lowercase().contains(&query)
+                    || song.verses.iter().flat_map(|x| x.lines.iter()).any(|x| x.to_lowercase().contains(&query))
+            })
+            .collect())
+    }
+
+    /// The song with exactly this title, ignoring case.
+    pub fn find(&self, title: &str) -> Result<Song, String> {
+        let title_lowercase = title.to_lowercase();
+
+        self.songs()?
+            .into_iter()
+            .find(|x| x.title.to_lowercase() == title_lowercase)
+            .ok_or(format!("'{title}' is not in the library"))
+    }
+
+    /// Saves the song, replacing a song with the same title and author.
+    pub fn add(&self, song: &Song) -> Result<PathBuf, String> {
+        fs::create_dir_all(&self.dir).map_err(|_| "Unable to create library directory".to_string())?;
+
+        let name = if song.author.is_empty() {
+            song.title.clone()
+        } else {
+            format!("{} - {}", song.title, song.author)
+        };
+        let path = self.dir.join(format!("{}.json", file_name_for(&name)));
+
+        let json = serde_json::to_string(song).map_err(|_| "Unable to convert song to JSON".to_string())?;
+        fs::write(&path, json).map_err(|_| format!("Unable to save '{}'", song.title))?;
+
+        Ok(path)
+    }
+}
+
+fn read_song(path: &Path) -> Result<Song, String> {
+    let json = fs::read_to_string(path).map_err(|_| format!("Unable to read {}", path.display()))?;
+
+    serde_json::from_str(&json).map_err(|_| format!("{} is not a song", path.display()))
+}
+
+/// A file name without characters that are not allowed on some systems.
+pub fn file_name_for(name: &str) -> String {
+    name.chars()
+        .map(|x| if x.is_alphanumeric() || x == '-' || x == ' ' { x } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::song;
+
+    #[test]
+    fn find_ignores_case_beyond_ascii() {
+        let dir = std::env::temp_dir().join(format!("beamer-library-test-{}", std::process::id()));
+        let library = Library::new(dir.clone());
+        library.add(&song("Ärger Über Ölbäume", &["la la"])).unwrap();
+
+        let found = library.find("ärger über ölbäume");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found.unwrap().title, "Ärger Über Ölbäume");
+    }
+}
@@ -0,0 +1,189 @@
+use std::vec;
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::Html;
+use tower_service::Service;
+use crate::song::{Song, Verse};
+
+const SEARCH_URL: &str = "https://api.genius.com/search";
+
+/// Finds the song on Genius and loads its lyrics. Never returns a song without verses.
+pub async fn search_song(author: &str, title: &str, genius_token: &str) -> Result<Song, String> {
+    let (song_url, actual_author, actual_title) = find_song_details(author, title, genius_token).await?;
+
+    let mut new_song = get_lyrics(
+        &song_url,
+        &actual_author,
+        &actual_title
+    ).await?;
+
+    if new_song.verses.is_empty() {
+        new_song.verses = vec![Verse::default()];
+    }
+
+    Ok(new_song)
+}
+
+/// Loads the lyrics from a Genius song page.
+pub async fn get_lyrics(song_url: &str, author: &str, title: &str) -> Result<Song, String> {
+    log::debug!("loading lyrics from {song_url}");
+    let song_url = reqwest::Url::parse(song_url).map_err(|x| {
+        log::warn!("invalid song URL {song_url}: {x}");
+        "Invalid URL".to_string()
+    })?;
+    let response = reqwest::get(song_url.to_string())
+        .await
+        .map_err(|x| {
+            log::warn!("could not load song page: {x}");
+            "Unable to load song page".to_string()
+        })?
+        .text()
+        .await
+        .map_err(|_| "Unable to extract text from song page".to_string())?;
+
+    let document = scraper::Html::parse_document(&response);
+
+    let verses = parse_song_text(&document, true);
+
+    Ok(Song {
+        title: title.to_string(),
+        author: author.to_string(),
+        verses,
+    })
+}
+
+/// Searches Genius for the song page.
+pub async fn find_song_details(
+    author: &str,
+    title: &str,
+    genius_token: &str,
+) -> Result<(String, String, String), String> { // Ok(url, actual author, actual title)
+    let search_string = format!("{} {}", author, title);
+
+    let mut client = reqwest::Client::new();
+    let request = client.get(SEARCH_URL)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .bearer_auth(genius_token)
+        .query(&[("q", search_string)])
+        .build()
+        .map_err(|x| x.to_string())?;
+
+    let response = client.call(request).await.map_err(|x| x.to_string())?;
+    log::debug!("search response status: {}", response.status());
+
+    let response_json: serde_json::Value = response.json().await.map_err(|x| x.to_string())?;
+    log::trace!("search response: {response_json}");
+
+    let mut url: String = "".to_string();
+    let mut actual_author: String = "".to_string();
+    let mut actual_title: String = "".to_string();
+    if let Some(hits) = response_json["response"]["hits"].as_array() {
+        // Only the best match
+        if let Some(hit) = hits.first() {
+            if hit["type"] == "song" {
+                match hit["result"]["url"].clone() {
+                    serde_json::Value::String(x) => url = x,
+                    _ => return Err("Failed to parse url to string".to_string()),
+                };
+
+                match hit["result"]["artist_names"].clone() {
+                    serde_json::Value::String(x) => actual_author = x,
+                    _ => return Err("Failed to parse artist_names to string".to_string()),
+                };
+
+                match hit["result"]["title"].clone() {
+                    serde_json::Value::String(x) => actual_title = x,
+                    _ => return Err("Failed to parse title to string".to_string()),
+                };
+            }
+        }
+
+        Ok((url, actual_author, actual_title))
+    } else {
+        Err("Song not found".to_string())
+    }
+}
+
+
+fn parse_song_text(document: &Html, remove_block_quotes: bool) -> Vec<Verse> {
+    // Lyrics__Container([a-zA-Z]|-|\d)+
+    // Remove all divs with contents: usually ads
+    lazy_static! {
+        static ref FIND_LYRICS_CONTAINER_CLASS_REGEX: Regex = Regex::new(r#"Lyrics__Container([a-zA-Z]|-|\d)+"#).unwrap();
+    }
+
+    let document_string = document.html();
+    let mut container_css_class = None;
+    if let Some(capture) = FIND_LYRICS_CONTAINER_CLASS_REGEX.captures(&document_string) {
+        if let Some(capture_match) = capture.get(0) {
+            container_css_class = Some(capture_match.as_str().to_string());
+        }
+    }
+
+    if container_css_class.is_none() {
+        return vec![Verse::default()];
+    }
+    let container_css_class = container_css_class.unwrap();
+    let lyrics_selector = scraper::Selector::parse(&format!(".{container_css_class}")).unwrap();
+
+    let html_sections = document.select(&lyrics_selector)
+        .map(|x| x.inner_html());
+
+    let verse_sections = html_sections
+        .flat_map(|x| {
+            let lines = x.split("<br><br>");
+            lines
+                .map(|line| line.to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let verses_lines = verse_sections.into_iter()
+        .map(|x| {
+            let lines = x.split("<br>");
+            lines
+                .map(|line| line.to_owned())
+                .collect::<Vec<_>>()
+        });
+
+    // Remove all divs with contents: usually ads
+    lazy_static! {
+        static ref REMOVE_DIV_REGEX: Regex = Regex::new("<div>.*</div>").unwrap();
+    }
+    let verses_lines = verses_lines.map(|verse| verse.into_iter().map(|line| REMOVE_DIV_REGEX.replace_all(&line, "").into_owned()));
+
+    // Remove all lingering tags, e.g.: <i> and </i>
+    // We want to keep the text in between
+    lazy_static! {
+        static ref REMOVE_TAG_REGEX: Regex = Regex::new("<.*?>").unwrap();
+    }
+    let verses_lines = verses_lines.map(|verse| verse.map(|line| REMOVE_TAG_REGEX.replace_all(&line, "").into_owned()));
+
+    // Remove all Blockquotes if that option is enabled, while making sure types stay consistent
+    let new_verses_lines = if remove_block_quotes {
+        lazy_static! {
+            static ref REMOVE_BLOCKQUOTES_REGEX: Regex = Regex::new(r#"\[.*?\]"#).unwrap();
+        }
+        verses_lines
+            .map(|verse| {
+                let a = verse.map(|line| REMOVE_BLOCKQUOTES_REGEX.replace_all(&line, "").into_owned());
+                a.collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    } else {
+        verses_lines
+            .map(|verse| verse.collect())
+            .collect::<Vec<_>>()
+            .into_iter()
+    };
+
+    // Remove empty lines
+    let verses_lines = new_verses_lines.map(|verse| verse.into_iter().filter(|line| !line.is_empty()));
+
+    verses_lines
+        // .map(|verse| verse.map(|line| line.into_owned()))
+        .map(|verse| Verse::new(verse.collect()))
+        .filter(|verse| !verse.is_empty())
+        .collect()
+}
@@ -0,0 +1,278 @@
+use crate::display_selection::{DisplaySelection, NavigationError, SelectionUpdate};
+use crate::events::EventSink;
+use crate::history::{describe_change, SongListHistory};
+use crate::setlist::{self, ChangeResult, SongListChange, SongListUpdate};
+use crate::song::{Song, SongList, SongSlot, SongSlotType};
+
+/// The song list with its undo history, and what is selected in it.
+/// Every change is sent to an `EventSink`, so it can be shown anywhere.
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub song_list: SongList,
+    pub history: SongListHistory,
+    pub new_song_id: u64,
+    pub currently_selected: DisplaySelection,
+    /// Sent with every selection update.
+    pub selection_version: u64,
+    /// Sent with every song list update.
+    pub song_list_version: u64,
+}
+
+impl Default for Session {
+    /// Starts with a single empty slot.
+    fn default() -> Self {
+        Self {
+            song_list: SongList {
+                songs: vec![
+                    SongSlot {
+                        id: 0,
+                        slot: SongSlotType::Empty,
+                    },
+                ],
+            },
+            history: SongListHistory::default(),
+            new_song_id: 1,
+            currently_selected: DisplaySelection::empty(),
+            selection_version: 0,
+            song_list_version: 0,
+        }
+    }
+}
+
+impl Session {
+    /// The current and next selection, with the version of the last update.
+    pub fn selection_update(&self) -> SelectionUpdate {
+        let mut next_display_selection = self.currently_selected;
+        next_display_selection.next(&self.song_list);
+
+        SelectionUpdate {
+            version: self.selection_version,
+            current: self.currently_selected.view(&self.song_list),
+            next: next_display_selection.view(&self.song_list),
+        }
+    }
+
+    /// Shows the current selection live, and sends it as a selection update.
+    pub fn emit_selection(&mut self, events: &dyn EventSink) {
+        events.live_slide_changed(&self.song_list, &self.currently_selected);
+        self.emit_display_selection(events);
+    }
+
+    /// Sends the current and next selection, e.g. after the song list changed.
+    pub fn emit_display_selection(&mut self, events: &dyn EventSink) {
+        self.selection_version += 1;
+        events.selection_changed(&self.selection_update());
+    }
+
+    /// Sends a change to the song list, or `None` when the whole list has to be loaded again.
+    fn emit_song_list(&mut self, events: &dyn EventSink, change: Option<SongListChange>) {
+        self.song_list_version += 1;
+        events.song_list_changed(&SongListUpdate {
+            version: self.song_list_version,
+            change,
+        });
+    }
+
+    /// Keeps the selection on its slot after the song list changed.
+    /// Returns whether the live slot was replaced or removed, so its timers can be restarted.
+    fn follow_song_list_change(&mut self, events: &dyn EventSink, previous_song_list: &SongList) -> bool {
+        let live_slot_changed = self.currently_selected.follow_slot(previous_song_list, &self.song_list);
+        if live_slot_changed {
+            self.emit_selection(events);
+        } else {
+            self.emit_display_selection(events);
+        }
+
+        live_slot_changed
+    }
+
+    /// Records a change to the song list for undo.
+    fn record_change(&mut self, events: &dyn EventSink, label: String, previous_song_list: SongList) {
+        self.history.record(label, previous_song_list);
+        events.history_changed(&self.history.labels());
+    }
+
+    /// Replaces the whole song list, e.g. with the list the main window sends after editing.
+    /// Returns whether the live slot changed, or an error if two slots have the same id.
+    pub fn replace_song_list(&mut self, events: &dyn EventSink, new_song_list: SongList) -> Result<bool, String> {
+        for (position, song_slot) in new_song_list.songs.iter().enumerate() {
+            if new_song_list.songs[..position].iter().any(|x| x.id == song_slot.id) {
+                return Err(format!("Two slots have the same id: {}", song_slot.id));
+            }
+        }
+        // Ids of new slots must not clash with the ones in the list
+        if let Some(max_id) = new_song_list.songs.iter().map(|x| x.id).max() {
+            self.new_song_id = self.new_song_id.max(max_id + 1);
+        }
+
+        let previous_song_list = std::mem::replace(&mut self.song_list, new_song_list);
+        if previous_song_list != self.song_list {
+            self.emit_song_list(events, None);
+        }
+        let live_slot_changed = self.follow_song_list_change(events, &previous_song_list);
+
+        if let Some(label) = describe_change(&previous_song_list, &self.song_list) {
+            self.record_change(events, label, previous_song_list);
+        }
+
+        Ok(live_slot_changed)
+    }
+
+    /// Appends `slot` to the song list.
+    pub fn add_slot(&mut self, events: &dyn EventSink, slot: SongSlotType) {
+        let previous_song_list = self.song_list.clone();
+        let label = format!("Added '{}'", slot.title());
+
+        self.song_list.songs.push(
+            SongSlot {
+                id: self.new_song_id,
+                slot,
+            }
+        );
+        self.new_song_id += 1;
+        self.record_change(events, label, previous_song_list);
+
+        self.emit_song_list(events, None);
+        self.emit_display_selection(events);
+    }
+
+    /// Applies `change`, records it for undo and keeps the selection on its slot.
+    /// Returns whether the live slot changed.
+    fn change_song_list(
+        &mut self,
+        events: &dyn EventSink,
+        change: impl FnOnce(&mut SongList, &mut u64) -> ChangeResult,
+    ) -> Result<bool, String> {
+        let previous_song_list = self.song_list.clone();
+        let (label, song_list_change) = change(&mut self.song_list, &mut self.new_song_id)?;
+
+        self.emit_song_list(events, Some(song_list_change));
+        let live_slot_changed = self.follow_song_list_change(events, &previous_song_list);
+        self.record_change(events, label, previous_song_list);
+        Ok(live_slot_changed)
+    }
+
+    pub fn insert_slot(&mut self, events: &dyn EventSink, position: usize, slot: SongSlotType) -> Result<bool, String> {
+        self.change_song_list(events, |song_list, new_song_id| setlist::insert_slot(song_list, new_song_id, position, slot))
+    }
+
+    pub fn move_slot(&mut self, events: &dyn EventSink, slot_id: u64, position: usize) -> Result<bool, String> {
+        self.change_song_list(events, |song_list, _| setlist::move_slot(song_list, slot_id, position))
+    }
+
+    pub fn remove_slot(&mut self, events: &dyn EventSink, slot_id: u64) -> Result<bool, String> {
+        self.change_song_list(events, |song_list, _| setlist::remove_slot(song_list, slot_id))
+    }
+
+    pub fn duplicate_slot(&mut self, events: &dyn EventSink, slot_id: u64) -> Result<bool, String> {
+        self.change_song_list(events, |song_list, new_song_id| setlist::duplicate_slot(song_list, new_song_id, slot_id))
+    }
+
+    pub fn replace_slot_song(&mut self, events: &dyn EventSink, slot_id: u64, song: Song) -> Result<bool, String> {
+        self.change_song_list(events, |song_list, _| setlist::replace_slot_song(song_list, slot_id, song))
+    }
+
+    /// Returns whether the live slot changed, like the other changes to the song list.
+    pub fn undo(&mut self, events: &dyn EventSink) -> Result<bool, String> {
+        self.undo_or_redo(events, false)
+    }
+
+    pub fn redo(&mut self, events: &dyn EventSink) -> Result<bool, String> {
+        self.undo_or_redo(events, true)
+    }
+
+    fn undo_or_redo(&mut self, events: &dyn EventSink, redo: bool) -> Result<bool, String> {
+        let current_song_list = self.song_list.clone();
+        let restored_song_list = if redo {
+            self.history.redo(current_song_list).ok_or("Nothing to redo".to_string())?
+        } else {
+            self.history.undo(current_song_list).ok_or("Nothing to undo".to_string())?
+        };
+        let previous_song_list = std::mem::replace(&mut self.song_list, restored_song_list);
+        events.history_changed(&self.history.labels());
+
+        self.emit_song_list(events, None);
+        Ok(self.follow_song_list_change(events, &previous_song_list))
+    }
+
+    pub fn select_next(&mut self, events: &dyn EventSink) {
+        self.currently_selected.next(&self.song_list);
+        self.emit_selection(events);
+    }
+
+    pub fn select_previous(&mut self, events: &dyn EventSink) {
+        self.currently_selected.previous(&self.song_list);
+        self.emit_selection(events);
+    }
+
+    /// Jumps to a verse of the slot with `slot_id`.
+    pub fn select_slot(&mut self, events: &dyn EventSink, slot_id: u64, verse_num: Option<usize>) -> Result<(), NavigationError> {
+        self.currently_selected = DisplaySelection::for_slot(&self.song_list, slot_id, verse_num)?;
+        self.emit_selection(events);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use super::*;
+    use crate::events::NoEvents;
+    use crate::test_util::{song_list, song_slot};
+
+    /// Keeps the song list updates, to check what a client would receive.
+    #[derive(Default)]
+    struct SongListUpdates(RefCell<Vec<SongListUpdate>>);
+
+    impl EventSink for SongListUpdates {
+        fn song_list_changed(&self, update: &SongListUpdate) {
+            self.0.borrow_mut().push(update.clone());
+        }
+    }
+
+    #[test]
+    fn song_list_updates_are_versioned() {
+        let events = SongListUpdates::default();
+        let mut session = Session::default();
+        session.replace_song_list(&events, song_list(vec![song_slot("A", 1)])).unwrap();
+        session.insert_slot(&events, 1, song_slot("B", 1)).unwrap();
+        session.undo(&events).unwrap();
+        // Unchanged: nothing to send
+        session.replace_song_list(&events, session.song_list.clone()).unwrap();
+
+        let updates = events.0.into_inner();
+        assert_eq!(updates.iter().map(|x| x.version).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(updates[0].change.is_none());
+        assert!(matches!(updates[1].change, Some(SongListChange::Inserted { position: 1, .. })));
+        assert!(updates[2].change.is_none());
+        assert_eq!(session.song_list_version, 3);
+    }
+
+    #[test]
+    fn removing_the_live_slot_is_reported() {
+        let mut session = Session::default();
+        session.replace_song_list(&NoEvents, song_list(vec![song_slot("A", 1), song_slot("B", 1)])).unwrap();
+        session.select_slot(&NoEvents, 1, None).unwrap();
+
+        assert!(!session.remove_slot(&NoEvents, 0).unwrap());
+        assert!(session.remove_slot(&NoEvents, 1).unwrap());
+        assert!(session.undo(&NoEvents).unwrap());
+    }
+
+    #[test]
+    fn replaced_song_lists_keep_slot_ids_unique() {
+        let mut session = Session::default();
+        let mut duplicate_ids = song_list(vec![song_slot("A", 1), song_slot("B", 1)]);
+        duplicate_ids.songs[1].id = 0;
+
+        assert!(session.replace_song_list(&NoEvents, duplicate_ids).is_err());
+        assert_eq!(session.song_list, Session::default().song_list);
+
+        let mut from_remote = song_list(vec![song_slot("A", 1), song_slot("B", 1)]);
+        from_remote.songs[1].id = 7;
+        session.replace_song_list(&NoEvents, from_remote).unwrap();
+        session.add_slot(&NoEvents, song_slot("C", 1));
+
+        assert_eq!(session.song_list.songs.iter().map(|x| x.id).collect::<Vec<_>>(), vec![0, 7, 8]);
+    }
+}
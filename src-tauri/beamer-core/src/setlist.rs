@@ -0,0 +1,312 @@
+use serde::Serialize;
+use crate::song::{Song, SongList, SongSlot, SongSlotType};
+
+/// A single change to the song list, so the main window does not have to reload the whole list.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "change", content = "payload")]
+pub enum SongListChange {
+    Inserted { position: usize, slot: SongSlot },
+    Moved { slot_id: u64, from: usize, to: usize },
+    Removed { slot_id: u64, position: usize },
+    Replaced { position: usize, slot: SongSlot },
+}
+
+/// Sent as `update-song-list`.
+#[derive(Clone, Debug, Serialize)]
+pub struct SongListUpdate {
+    /// Goes up by one with every change, so a client can tell it missed one.
+    pub version: u64,
+    /// `None` when the whole list was replaced, e.g. by undo: the list has to be loaded again.
+    #[serde(flatten)]
+    pub change: Option<SongListChange>,
+}
+
+/// What a change did, for undo, and the change itself.
+pub type ChangeResult = Result<(String, SongListChange), String>;
+
+fn find_slot(song_list: &SongList, slot_id: u64) -> Result<usize, String> {
+    song_list.songs.iter()
+        .position(|x| x.id == slot_id)
+        .ok_or(format!("Slot {slot_id} not found"))
+}
+
+fn check_song(song: &Song) -> Result<(), String> {
+    if song.verses.is_empty() {
+        return Err(format!("'{}' has no verses", song.title));
+    }
+    Ok(())
+}
+
+pub fn insert_slot(
+    song_list: &mut SongList,
+    new_song_id: &mut u64,
+    position: usize,
+    slot: SongSlotType,
+) -> ChangeResult {
+    if let SongSlotType::Song(ref song) = slot {
+        check_song(song)?;
+    }
+    if position > song_list.songs.len() {
+        return Err(format!("Position {position} is past the end of the song list"));
+    }
+
+    let song_slot = SongSlot {
+        id: *new_song_id,
+        slot,
+    };
+    *new_song_id += 1;
+    song_list.songs.insert(position, song_slot.clone());
+
+    Ok((
+        format!("Inserted '{}'", song_slot.slot.title()),
+        SongListChange::Inserted { position, slot: song_slot },
+    ))
+}
+
+pub fn move_slot(
+    song_list: &mut SongList,
+    slot_id: u64,
+    position: usize,
+) -> ChangeResult {
+    let from = find_slot(song_list, slot_id)?;
+    if position >= song_list.songs.len() {
+        return Err(format!("Position {position} is past the end of the song list"));
+    }
+
+    let song_slot = song_list.songs.remove(from);
+    let label = format!("Moved '{}'", song_slot.slot.title());
+    song_list.songs.insert(position, song_slot);
+
+    Ok((label, SongListChange::Moved { slot_id, from, to: position }))
+}
+
+pub fn remove_slot(
+    song_list: &mut SongList,
+    slot_id: u64,
+) -> ChangeResult {
+    let position = find_slot(song_list, slot_id)?;
+    let song_slot = song_list.songs.remove(position);
+
+    Ok((
+        format!("Removed '{}'", song_slot.slot.title()),
+        SongListChange::Removed { slot_id, position },
+    ))
+}
+
+/// Inserts a copy with a new id right after the original.
+pub fn duplicate_slot(
+    song_list: &mut SongList,
+    new_song_id: &mut u64,
+    slot_id: u64,
+) -> ChangeResult {
+    let position = find_slot(song_list, slot_id)? + 1;
+
+    let song_slot = SongSlot {
+        id: *new_song_id,
+        slot: song_list.songs[position - 1].slot.clone(),
+    };
+    *new_song_id += 1;
+    song_list.songs.insert(position, song_slot.clone());
+
+    Ok((
+        format!("Duplicated '{}'", song_slot.slot.title()),
+        SongListChange::Inserted { position, slot: song_slot },
+    ))
+}
+
+/// Keeps the slot id, so a live slot stays live with the new lyrics.
+pub fn replace_slot_song(
+    song_list: &mut SongList,
+    slot_id: u64,
+    song: Song,
+) -> ChangeResult {
+    check_song(&song)?;
+    let position = find_slot(song_list, slot_id)?;
+
+    let song_slot = &mut song_list.songs[position];
+    let label = format!("Replaced '{}' with '{}'", song_slot.slot.title(), song.title);
+    song_slot.slot = SongSlotType::Song(song);
+
+    Ok((label, SongListChange::Replaced { position, slot: song_slot.clone() }))
+}
+
+/// Problems that would show up during the service, e.g. a song without verses or a missing image.
+/// Returns an empty list for a song list that can be used as is.
+pub fn validate_song_list(song_list: &SongList) -> Vec<String> {
+    let mut problems = vec![];
+
+    for (position, song_slot) in song_list.songs.iter().enumerate() {
+        let name = format!("Slot {} ('{}')", position + 1, song_slot.slot.title());
+
+        if song_list.songs[..position].iter().any(|x| x.id == song_slot.id) {
+            problems.push(format!("{name} has the same id as an earlier slot: {}", song_slot.id));
+        }
+
+        match song_slot.slot {
+            SongSlotType::Empty => {},
+            SongSlotType::Song(ref song) => {
+                if song.verses.is_empty() {
+                    problems.push(format!("{name} has no verses"));
+                } else if song.verses.iter().all(|x| x.is_empty()) {
+                    problems.push(format!("{name} has no lyrics"));
+                }
+            },
+            SongSlotType::Image(ref image) => {
+                if !image.path.is_file() {
+                    problems.push(format!("{name} shows an image that does not exist: {}", image.path.display()));
+                }
+            },
+            SongSlotType::CustomSlides(ref custom_slides) => {
+                if custom_slides.slides.is_empty() {
+                    problems.push(format!("{name} has no slides"));
+                }
+            },
+            SongSlotType::Bible(ref passage) => {
+                if passage.slides.is_empty() {
+                    problems.push(format!("{name} has no slides"));
+                }
+            },
+            SongSlotType::Countdown(ref countdown) => {
+                if let Some(problem) = countdown.target.problem() {
+                    problems.push(format!("{name} {problem}"));
+                }
+            },
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use crate::song::{Countdown, CountdownEndAction, CountdownTarget, CustomSlides, ImageScaling, ImageSlide};
+    use crate::test_util::{song, song_list, song_slot};
+
+    fn ids(song_list: &SongList) -> Vec<u64> {
+        song_list.songs.iter().map(|x| x.id).collect()
+    }
+
+    #[test]
+    fn insert_slot_gives_a_new_id() {
+        let mut song_list = song_list(vec![song_slot("A", 1), song_slot("B", 1)]);
+        let mut new_song_id = 2;
+
+        let (label, _) = insert_slot(&mut song_list, &mut new_song_id, 1, song_slot("C", 1)).unwrap();
+        assert_eq!(label, "Inserted 'C'");
+        assert_eq!(ids(&song_list), vec![0, 2, 1]);
+        assert_eq!(new_song_id, 3);
+    }
+
+    #[test]
+    fn insert_slot_rejects_songs_without_verses_and_positions_past_the_end() {
+        let mut song_list = song_list(vec![song_slot("A", 1)]);
+        let mut new_song_id = 1;
+
+        assert!(insert_slot(&mut song_list, &mut new_song_id, 0, song_slot("Empty", 0)).is_err());
+        assert!(insert_slot(&mut song_list, &mut new_song_id, 2, song_slot("B", 1)).is_err());
+        assert_eq!(ids(&song_list), vec![0]);
+        assert_eq!(new_song_id, 1);
+    }
+
+    #[test]
+    fn move_slot_rejects_unknown_slots_and_positions_past_the_end() {
+        let mut song_list = song_list(vec![song_slot("A", 1), song_slot("B", 1)]);
+
+        assert!(move_slot(&mut song_list, 5, 0).is_err());
+        assert!(move_slot(&mut song_list, 0, 2).is_err());
+        assert_eq!(ids(&song_list), vec![0, 1]);
+
+        move_slot(&mut song_list, 0, 1).unwrap();
+        assert_eq!(ids(&song_list), vec![1, 0]);
+    }
+
+    #[test]
+    fn remove_slot_rejects_unknown_slots() {
+        let mut song_list = song_list(vec![song_slot("A", 1)]);
+
+        assert!(remove_slot(&mut song_list, 5).is_err());
+        remove_slot(&mut song_list, 0).unwrap();
+        assert!(song_list.songs.is_empty());
+    }
+
+    #[test]
+    fn duplicate_slot_inserts_a_copy_after_the_original() {
+        let mut song_list = song_list(vec![song_slot("A", 1), song_slot("B", 1)]);
+        let mut new_song_id = 2;
+
+        duplicate_slot(&mut song_list, &mut new_song_id, 0).unwrap();
+        assert_eq!(ids(&song_list), vec![0, 2, 1]);
+        assert_eq!(song_list.songs[1].slot, song_list.songs[0].slot);
+    }
+
+    #[test]
+    fn replace_slot_song_keeps_the_id() {
+        let mut song_list = song_list(vec![song_slot("A", 1)]);
+
+        assert!(replace_slot_song(&mut song_list, 0, song("Empty", &[])).is_err());
+        replace_slot_song(&mut song_list, 0, song("B", &["b"])).unwrap();
+        assert_eq!(song_list.songs[0].id, 0);
+        assert_eq!(song_list.songs[0].slot.title(), "B");
+    }
+
+    #[test]
+    fn song_list_update_has_the_change_next_to_the_version() {
+        let update = SongListUpdate {
+            version: 3,
+            change: Some(SongListChange::Removed { slot_id: 1, position: 0 }),
+        };
+        assert_eq!(
+            serde_json::to_string(&update).unwrap(),
+            r#"{"version":3,"change":"Removed","payload":{"slot_id":1,"position":0}}"#,
+        );
+
+        let reload = SongListUpdate { version: 4, change: None };
+        assert_eq!(serde_json::to_string(&reload).unwrap(), r#"{"version":4}"#);
+    }
+
+    #[test]
+    fn validate_song_list_accepts_a_valid_list() {
+        let song_list = song_list(vec![SongSlotType::Empty, song_slot("A", 2)]);
+
+        assert!(validate_song_list(&song_list).is_empty());
+    }
+
+    #[test]
+    fn validate_song_list_reports_every_problem() {
+        let countdown = |target| SongSlotType::Countdown(Countdown {
+            label: "Start".to_string(),
+            target,
+            end_action: CountdownEndAction::StayOnZero,
+        });
+        let mut song_list = song_list(vec![
+            song_slot("No verses", 0),
+            SongSlotType::Song(Song::new("No lyrics", "", vec![Default::default()])),
+            SongSlotType::Image(ImageSlide {
+                title: "Missing".to_string(),
+                path: PathBuf::from("/does/not/exist.png"),
+                scaling: ImageScaling::Fit,
+            }),
+            SongSlotType::CustomSlides(CustomSlides {
+                title: "No slides".to_string(),
+                slides: vec![],
+            }),
+            countdown(CountdownTarget::Duration { seconds: 0 }),
+            countdown(CountdownTarget::WallClock { hour: 24, minute: 0 }),
+            song_slot("Same id", 1),
+        ]);
+        song_list.songs[6].id = 0;
+
+        let problems = validate_song_list(&song_list);
+        assert_eq!(problems, vec![
+            "Slot 1 ('No verses') has no verses",
+            "Slot 2 ('No lyrics') has no lyrics",
+            "Slot 3 ('Missing') shows an image that does not exist: /does/not/exist.png",
+            "Slot 4 ('No slides') has no slides",
+            "Slot 5 ('Start') counts down from zero",
+            "Slot 6 ('Start') counts down to an invalid time: 24:00",
+            "Slot 7 ('Same id') has the same id as an earlier slot: 0",
+        ]);
+    }
+}
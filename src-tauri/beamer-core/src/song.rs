@@ -0,0 +1,414 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SongList {
+    pub songs: Vec<SongSlot>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum SongSlotType {
+    Empty,
+    Song(Song),
+    Image(ImageSlide),
+    CustomSlides(CustomSlides),
+    Bible(BiblePassage),
+    Countdown(Countdown),
+}
+
+impl SongSlotType {
+    pub fn num_verses(&self) -> usize {
+        match self {
+            Self::Empty => 0,
+            Self::Song(ref song) => song.num_verses(),
+            Self::Image(_) => 1,
+            Self::CustomSlides(ref custom_slides) => custom_slides.slides.len(),
+            Self::Bible(ref passage) => passage.slides.len(),
+            Self::Countdown(_) => 1,
+        }
+    }
+
+    /// How the slot is named in the song list.
+    pub fn title(&self) -> &str {
+        match self {
+            Self::Empty => "Empty Slide",
+            Self::Song(ref song) => &song.title,
+            Self::Image(ref image) => &image.title,
+            Self::CustomSlides(ref custom_slides) => &custom_slides.title,
+            Self::Bible(ref passage) => &passage.reference,
+            Self::Countdown(ref countdown) => &countdown.label,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SongSlot {
+    pub id: u64,
+    pub slot: SongSlotType,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Song {
+    pub title: String,
+    pub author: String,
+    pub verses: Vec<Verse>,
+}
+
+/// An image shown full-screen, the file lives in the `images` folder of the app data dir.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ImageSlide {
+    pub title: String,
+    pub path: PathBuf,
+    pub scaling: ImageScaling,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ImageScaling {
+    Fit,
+    Fill,
+    Stretch,
+}
+
+/// A scripture reading, already split into slides.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BiblePassage {
+    pub reference: String,
+    pub bible_name: String,
+    pub slides: Vec<Verse>,
+}
+
+/// Counts down to zero, e.g. before the start of the service.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Countdown {
+    pub label: String,
+    pub target: CountdownTarget,
+    pub end_action: CountdownEndAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum CountdownTarget {
+    /// Counts down from the moment the countdown goes live.
+    Duration { seconds: u64 },
+    /// Counts down to a local time today.
+    WallClock { hour: u32, minute: u32 },
+}
+
+impl CountdownTarget {
+    /// Why this target can't be counted down to, e.g. "counts down from zero".
+    pub fn problem(&self) -> Option<String> {
+        match *self {
+            CountdownTarget::Duration { seconds: 0 } => Some("counts down from zero".to_string()),
+            CountdownTarget::WallClock { hour, minute } if hour > 23 || minute > 59 => {
+                Some(format!("counts down to an invalid time: {hour}:{minute:02}"))
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum CountdownEndAction {
+    StayOnZero,
+    AdvanceToNextSlot,
+}
+
+/// Free-text slides, e.g. for welcome texts and announcements.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CustomSlides {
+    pub title: String,
+    pub slides: Vec<CustomSlide>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CustomSlide {
+    pub blocks: Vec<TextBlock>,
+    /// Automatically go to the next slide after this many seconds.
+    pub auto_advance_seconds: Option<u64>,
+}
+
+/// Rich text is stored as styled spans instead of HTML, so it can be rendered safely.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TextBlock {
+    pub style: BlockStyle,
+    pub spans: Vec<TextSpan>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockStyle {
+    Paragraph,
+    Heading,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Verse {
+    pub lines: Vec<String>,
+}
+
+impl Song {
+    pub fn new(title: &str, author: &str, verses: Vec<Verse>) -> Self {
+        Self {
+            title: title.to_string(),
+            author: author.to_string(),
+            verses,
+        }
+    }
+
+    pub fn num_verses(&self) -> usize {
+        self.verses.len()
+    }
+
+    pub fn from_song_addition(addition: SongAddition) -> Self {
+        Self {
+            title: addition.title,
+            author: addition.author,
+            verses: song_text_to_verses(addition.song_text),
+        }
+    }
+
+    /// The verses with an empty line in between, as typed in the main window.
+    pub fn to_text(&self) -> String {
+        self.verses.iter()
+            .map(|verse| verse.lines.join("\n"))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    pub fn to_chordpro(&self) -> String {
+        let mut chordpro = format!("{{title: {}}}\n", self.title);
+        if !self.author.is_empty() {
+            chordpro.push_str(&format!("{{artist: {}}}\n", self.author));
+        }
+        chordpro.push('\n');
+        chordpro.push_str(&self.to_text());
+        chordpro.push('\n');
+
+        chordpro
+    }
+}
+
+impl Verse {
+    pub fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+
+impl CustomSlides {
+    pub fn from_custom_slides_addition(addition: CustomSlidesAddition) -> Self {
+        let mut slides = addition.slides.into_iter()
+            .map(|slide| CustomSlide {
+                blocks: rich_text_to_blocks(&slide.text),
+                auto_advance_seconds: slide.auto_advance_seconds.filter(|x| *x > 0),
+            })
+            .collect::<Vec<_>>();
+
+        if slides.is_empty() {
+            slides.push(CustomSlide::default());
+        }
+
+        Self {
+            title: addition.title,
+            slides,
+        }
+    }
+}
+
+impl CustomSlide {
+    /// Plain text version of the slide.
+    pub fn to_verse(&self) -> Verse {
+        Verse::new(self.blocks.iter().map(TextBlock::plain_text).collect())
+    }
+}
+
+impl TextBlock {
+    pub fn plain_text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SongAddition {
+    pub title: String,
+    pub author: String,
+    pub song_text: String, // To be parsed into verses.
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CustomSlidesAddition {
+    pub title: String,
+    pub slides: Vec<CustomSlideAddition>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CustomSlideAddition {
+    pub text: String, // To be parsed into text blocks.
+    pub auto_advance_seconds: Option<u64>,
+}
+
+
+pub fn song_text_to_verses(song_text: String) -> Vec<Verse> {
+    let lines = song_text.lines();
+    let mut verses = vec![];
+
+    let mut current_verse_lines = vec![];
+
+    for line in lines {
+        if line.is_empty() {
+            verses.push(Verse::new(current_verse_lines.clone()));
+            current_verse_lines = vec![];
+        } else {
+            current_verse_lines.push(line.to_owned());
+        }
+    }
+
+    verses.push(Verse::new(current_verse_lines));
+
+    if verses.is_empty() {
+        verses.push(Verse::default());
+    }
+
+    verses
+}
+
+
+/// Parses a small markdown-like syntax: lines starting with `#` are headings,
+/// `**bold**` and `*italic*` style the text and `\` escapes the next character.
+pub fn rich_text_to_blocks(text: &str) -> Vec<TextBlock> {
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if let Some(heading) = line.strip_prefix('#') {
+                TextBlock {
+                    style: BlockStyle::Heading,
+                    spans: text_to_spans(heading.trim_start_matches('#').trim_start()),
+                }
+            } else {
+                TextBlock {
+                    style: BlockStyle::Paragraph,
+                    spans: text_to_spans(line),
+                }
+            }
+        })
+        .collect()
+}
+
+fn text_to_spans(line: &str) -> Vec<TextSpan> {
+    let mut spans = vec![];
+    let mut current_text = String::new();
+    let mut bold = false;
+    let mut italic = false;
+
+    let mut chars = line.chars().peekable();
+    while let Some(character) = chars.next() {
+        match character {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    current_text.push(escaped);
+                }
+            },
+            '*' => {
+                if !current_text.is_empty() {
+                    spans.push(TextSpan {
+                        text: current_text,
+                        bold,
+                        italic,
+                    });
+                    current_text = String::new();
+                }
+
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    bold = !bold;
+                } else {
+                    italic = !italic;
+                }
+            },
+            _ => current_text.push(character),
+        }
+    }
+
+    if !current_text.is_empty() {
+        spans.push(TextSpan {
+            text: current_text,
+            bold,
+            italic,
+        });
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, bold: bool, italic: bool) -> TextSpan {
+        TextSpan { text: text.to_string(), bold, italic }
+    }
+
+    #[test]
+    fn text_to_spans_styles_bold_and_italic() {
+        assert_eq!(text_to_spans("plain **bold** and *italic*"), vec![
+            span("plain ", false, false),
+            span("bold", true, false),
+            span(" and ", false, false),
+            span("italic", false, true),
+        ]);
+    }
+
+    #[test]
+    fn text_to_spans_nests_styles() {
+        assert_eq!(text_to_spans("**bold *both* bold** *italic **both***"), vec![
+            span("bold ", true, false),
+            span("both", true, true),
+            span(" bold", true, false),
+            span(" ", false, false),
+            span("italic ", false, true),
+            span("both", true, true),
+        ]);
+    }
+
+    #[test]
+    fn text_to_spans_keeps_unclosed_styles_to_the_end() {
+        assert_eq!(text_to_spans("a **b"), vec![span("a ", false, false), span("b", true, false)]);
+        assert_eq!(text_to_spans("*"), vec![]);
+        assert_eq!(text_to_spans(r"2 \* 3 \\"), vec![span(r"2 * 3 \", false, false)]);
+    }
+
+    #[test]
+    fn rich_text_to_blocks_splits_lines_and_skips_blank_ones() {
+        let blocks = rich_text_to_blocks("## Welcome\n\n  first line  \n\n\n*second* line\n");
+
+        assert_eq!(blocks, vec![
+            TextBlock { style: BlockStyle::Heading, spans: vec![span("Welcome", false, false)] },
+            TextBlock { style: BlockStyle::Paragraph, spans: vec![span("first line", false, false)] },
+            TextBlock {
+                style: BlockStyle::Paragraph,
+                spans: vec![span("second", false, true), span(" line", false, false)],
+            },
+        ]);
+        assert!(rich_text_to_blocks("\n \n").is_empty());
+    }
+
+    #[test]
+    fn styles_do_not_carry_over_to_the_next_block() {
+        let blocks = rich_text_to_blocks("**unclosed\nplain");
+
+        assert_eq!(blocks[1].spans, vec![span("plain", false, false)]);
+    }
+}
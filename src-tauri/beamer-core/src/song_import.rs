@@ -0,0 +1,320 @@
+use std::fs;
+use std::path::Path;
+use lazy_static::lazy_static;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use regex::Regex;
+use crate::song::{song_text_to_verses, Song, Verse};
+use crate::xml::{attribute, collapse_whitespace};
+
+/// Imports a song file, detecting the format from the extension: plain text, ChordPro or OpenLyrics.
+/// The file name is the title when the file does not contain one.
+pub fn import_song_file(path: &Path) -> Result<Song, String> {
+    let text = fs::read_to_string(path).map_err(|_| format!("Unable to read {}", path.display()))?;
+    let fallback_title = path.file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or("Song".to_string());
+    let extension = path.extension()
+        .map(|x| x.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "txt" => Ok(import_text(&text, &fallback_title)),
+        "cho" | "chordpro" | "chopro" | "crd" | "pro" => import_chordpro(&text, &fallback_title),
+        "xml" => import_openlyrics(&text, &fallback_title),
+        _ => Err(format!("Unknown song format for {}: expected .txt, ChordPro or OpenLyrics", path.display())),
+    }
+}
+
+/// Plain text, with an empty line between verses, as typed in the main window.
+pub fn import_text(text: &str, title: &str) -> Song {
+    Song::new(title, "", without_empty_verses(song_text_to_verses(text.to_string())))
+}
+
+/// ChordPro: `{title: ...}` and `{artist: ...}` directives, with chords like `[G]` in the lyrics.
+/// Sections, e.g. `{start_of_chorus}`, and empty lines start a new verse. Comments and tabs are left out.
+pub fn import_chordpro(text: &str, fallback_title: &str) -> Result<Song, String> {
+    lazy_static! {
+        static ref DIRECTIVE_REGEX: Regex = Regex::new(r"^\{\s*([A-Za-z_]+)\s*(?::\s*(.*?))?\s*\}$").unwrap();
+        static ref CHORD_REGEX: Regex = Regex::new(r"\[[^\]]*\]").unwrap();
+    }
+
+    let mut title = None;
+    let mut author = None;
+    let mut verses = vec![];
+    let mut current_lines: Vec<String> = vec![];
+    // Inside tabs or grids, which are only useful to the musicians
+    let mut skipping = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if let Some(captures) = DIRECTIVE_REGEX.captures(line) {
+            let value = captures.get(2).map(|x| x.as_str().to_string());
+
+            match captures[1].to_lowercase().as_str() {
+                "title" | "t" => title = value,
+                "artist" | "composer" | "lyricist" if author.is_none() => author = value,
+                "start_of_tab" | "sot" | "start_of_grid" | "sog" => skipping = true,
+                "end_of_tab" | "eot" | "end_of_grid" | "eog" => skipping = false,
+                name if name.starts_with("start_of_") || name.starts_with("end_of_")
+                    || ["soc", "eoc", "sov", "eov", "sob", "eob"].contains(&name) => {
+                    finish_verse(&mut verses, &mut current_lines);
+                },
+                // Comments, chord definitions and layout
+                _ => {},
+            }
+            continue;
+        }
+
+        if skipping || line.starts_with('#') {
+            continue;
+        }
+
+        if line.is_empty() {
+            finish_verse(&mut verses, &mut current_lines);
+        } else {
+            let lyrics = collapse_whitespace(&CHORD_REGEX.replace_all(line, ""));
+            // Lines with only chords
+            if !lyrics.is_empty() {
+                current_lines.push(lyrics);
+            }
+        }
+    }
+    finish_verse(&mut verses, &mut current_lines);
+
+    if verses.is_empty() {
+        return Err("No lyrics found in ChordPro file".to_string());
+    }
+
+    Ok(Song::new(
+        title.as_deref().filter(|x| !x.is_empty()).unwrap_or(fallback_title),
+        author.as_deref().unwrap_or(""),
+        verses,
+    ))
+}
+
+/// OpenLyrics: `<song><properties><titles><title>` and `<authors><author>`, with the lyrics in
+/// `<lyrics><verse name="v1"><lines>line<br/>line</lines></verse>`. Every `<lines>` becomes a slide,
+/// in the order of the `verseOrder` property when there is one.
+pub fn import_openlyrics(xml: &str, fallback_title: &str) -> Result<Song, String> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut title: Option<String> = None;
+    let mut authors: Vec<String> = vec![];
+    let mut verse_order: Option<String> = None;
+    // Name of each verse, with its slides
+    let mut named_verses: Vec<(String, Vec<Verse>)> = vec![];
+
+    let mut found_song = false;
+    let mut current_element = String::new();
+    let mut current_text = String::new();
+    let mut current_lines: Option<Vec<String>> = None;
+    // Depth inside elements whose text is not part of the lyrics, e.g. comments
+    let mut skip_depth = 0;
+
+    loop {
+        let event = reader.read_event().map_err(|x| format!("Invalid XML: {x}"))?;
+
+        match event {
+            Event::Start(ref element) | Event::Empty(ref element) => {
+                let is_empty = matches!(event, Event::Empty(_));
+
+                match element.local_name().as_ref() {
+                    b"song" => found_song = true,
+                    b"verse" => named_verses.push((attribute(element, "name").unwrap_or_default(), vec![])),
+                    b"lines" if !is_empty => {
+                        current_lines = Some(vec![]);
+                        current_text.clear();
+                    },
+                    b"br" => {
+                        if let Some(ref mut lines) = current_lines {
+                            lines.push(collapse_whitespace(&current_text));
+                            current_text.clear();
+                        }
+                    },
+                    b"comment" if !is_empty => skip_depth += 1,
+                    name if !is_empty => current_element = String::from_utf8_lossy(name).into_owned(),
+                    _ => {},
+                }
+            },
+            Event::End(ref element) => {
+                match element.local_name().as_ref() {
+                    b"lines" => {
+                        if let Some(mut lines) = current_lines.take() {
+                            lines.push(collapse_whitespace(&current_text));
+                            lines.retain(|x| !x.is_empty());
+
+                            if let Some((_, slides)) = named_verses.last_mut() {
+                                if !lines.is_empty() {
+                                    slides.push(Verse::new(lines));
+                                }
+                            }
+                        }
+                    },
+                    b"comment" => skip_depth -= 1,
+                    _ => {},
+                }
+                current_element.clear();
+            },
+            Event::Text(ref text) => {
+                let text = text.unescape().map_err(|x| format!("Invalid XML: {x}"))?;
+
+                if current_lines.is_some() {
+                    if skip_depth == 0 {
+                        current_text.push_str(&text);
+                    }
+                } else {
+                    match current_element.as_str() {
+                        // The first title is the main title
+                        "title" if title.is_none() => title = Some(collapse_whitespace(&text)),
+                        "author" => authors.push(collapse_whitespace(&text)),
+                        "verseOrder" => verse_order = Some(text.into_owned()),
+                        _ => {},
+                    }
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    if !found_song {
+        return Err("Not an OpenLyrics file: expected a <song> element".to_string());
+    }
+
+    let verses = match verse_order {
+        Some(ref verse_order) if !verse_order.trim().is_empty() => verse_order.split_whitespace()
+            .filter_map(|name| named_verses.iter().find(|(x, _)| x.eq_ignore_ascii_case(name)))
+            .flat_map(|(_, slides)| slides.iter().cloned())
+            .collect::<Vec<_>>(),
+        _ => named_verses.into_iter().flat_map(|(_, slides)| slides).collect(),
+    };
+
+    if verses.is_empty() {
+        return Err("No lyrics found in OpenLyrics file".to_string());
+    }
+
+    Ok(Song::new(
+        title.as_deref().filter(|x| !x.is_empty()).unwrap_or(fallback_title),
+        &authors.join(", "),
+        verses,
+    ))
+}
+
+fn finish_verse(verses: &mut Vec<Verse>, current_lines: &mut Vec<String>) {
+    if !current_lines.is_empty() {
+        verses.push(Verse::new(std::mem::take(current_lines)));
+    }
+}
+
+/// Multiple empty lines between verses are not extra verses. Keeps one empty verse for an empty file.
+fn without_empty_verses(verses: Vec<Verse>) -> Vec<Verse> {
+    let mut verses = verses.into_iter()
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+
+    if verses.is_empty() {
+        verses.push(Verse::default());
+    }
+
+    verses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(song: &Song) -> Vec<Vec<&str>> {
+        song.verses.iter()
+            .map(|verse| verse.lines.iter().map(|x| x.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn import_text_splits_verses_on_empty_lines() {
+        let song = import_text("one\ntwo\n\n\n\nthree\n", "Title");
+
+        assert_eq!(song.title, "Title");
+        assert_eq!(lines(&song), vec![vec!["one", "two"], vec!["three"]]);
+        assert_eq!(import_text("", "Empty").verses, vec![Verse::default()]);
+    }
+
+    #[test]
+    fn import_chordpro_leaves_out_chords_comments_and_tabs() {
+        let chordpro = "\
+{title: Amazing Grace}
+{artist: John Newton}
+{composer: Someone else}
+# a comment
+{comment: Capo 2}
+
+[G]Amazing [G7]grace, how [C]sweet the [G]sound
+[G] [D]
+
+{start_of_tab}
+e|---0---|
+{end_of_tab}
+{start_of_chorus}
+I once was lost
+{end_of_chorus}
+";
+        let song = import_chordpro(chordpro, "Fallback").unwrap();
+
+        assert_eq!(song.title, "Amazing Grace");
+        assert_eq!(song.author, "John Newton");
+        assert_eq!(lines(&song), vec![vec!["Amazing grace, how sweet the sound"], vec!["I once was lost"]]);
+    }
+
+    #[test]
+    fn import_chordpro_uses_the_fallback_title_and_needs_lyrics() {
+        // A subtitle is not an author
+        let song = import_chordpro("{t:}\n{subtitle: Hymn 123}\nla la", "Fallback").unwrap();
+        assert_eq!(song.title, "Fallback");
+        assert_eq!(song.author, "");
+
+        assert!(import_chordpro("{title: Only chords}\n[G] [C]\n", "Fallback").is_err());
+    }
+
+    #[test]
+    fn import_openlyrics_follows_the_verse_order() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<song xmlns="http://openlyrics.info/namespace/2009/song" version="0.9">
+  <properties>
+    <titles><title>Amazing Grace</title><title>Alternative</title></titles>
+    <authors><author>John Newton</author><author>Someone &amp; Else</author></authors>
+    <verseOrder>v1 c v1</verseOrder>
+  </properties>
+  <lyrics>
+    <verse name="v1"><lines>Amazing grace<br/>how <chord name="C"/>sweet<comment>softly</comment></lines></verse>
+    <verse name="c"><lines>I once was lost</lines><lines>but now am found</lines></verse>
+  </lyrics>
+</song>"#;
+        let song = import_openlyrics(xml, "Fallback").unwrap();
+
+        assert_eq!(song.title, "Amazing Grace");
+        assert_eq!(song.author, "John Newton, Someone & Else");
+        assert_eq!(lines(&song), vec![
+            vec!["Amazing grace", "how sweet"],
+            vec!["I once was lost"],
+            vec!["but now am found"],
+            vec!["Amazing grace", "how sweet"],
+        ]);
+    }
+
+    #[test]
+    fn import_openlyrics_without_verse_order_keeps_the_file_order() {
+        let xml = r#"<song><lyrics><verse name="v2"><lines>two</lines></verse><verse name="v1"><lines>one</lines></verse></lyrics></song>"#;
+        let song = import_openlyrics(xml, "Fallback").unwrap();
+
+        assert_eq!(song.title, "Fallback");
+        assert_eq!(lines(&song), vec![vec!["two"], vec!["one"]]);
+    }
+
+    #[test]
+    fn import_openlyrics_rejects_other_xml_and_songs_without_lyrics() {
+        assert!(import_openlyrics("<bible></bible>", "Fallback").is_err());
+        assert!(import_openlyrics("<song><lyrics></lyrics></song>", "Fallback").is_err());
+        assert!(import_openlyrics("<song><lyrics>", "Fallback").is_err());
+    }
+}
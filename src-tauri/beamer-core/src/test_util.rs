@@ -0,0 +1,22 @@
+use crate::song::{Song, SongList, SongSlot, SongSlotType, Verse};
+
+/// A song with one single-line verse per entry in `verses`.
+pub fn song(title: &str, verses: &[&str]) -> Song {
+    Song::new(title, "", verses.iter().map(|x| Verse::new(vec![x.to_string()])).collect())
+}
+
+/// A song slot with `num_verses` verses, named after its title.
+pub fn song_slot(title: &str, num_verses: usize) -> SongSlotType {
+    let verses = (1..=num_verses).map(|x| format!("{title} {x}")).collect::<Vec<_>>();
+    SongSlotType::Song(song(title, &verses.iter().map(|x| x.as_str()).collect::<Vec<_>>()))
+}
+
+/// A song list with ids 0, 1, 2, ... in order.
+pub fn song_list(slots: Vec<SongSlotType>) -> SongList {
+    SongList {
+        songs: slots.into_iter()
+            .enumerate()
+            .map(|(id, slot)| SongSlot { id: id as u64, slot })
+            .collect(),
+    }
+}
@@ -0,0 +1,15 @@
+use quick_xml::events::BytesStart;
+
+/// The unescaped value of an attribute, `None` when it is missing or invalid.
+pub fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    element.try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|x| x.unescape_value().ok())
+        .map(|x| x.into_owned())
+}
+
+/// Joins the words with single spaces, since line breaks and indentation in XML text are layout.
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use beamer_core::display_selection::DisplaySelection;
+use crate::{AppState, ProgramState};
+use crate::countdown::start_countdown;
+use crate::events::{emit_to, WindowEvents};
+
+/// Pending automatic navigation. Every navigation increments `generation`,
+/// which cancels timers that were scheduled for an earlier selection.
+#[derive(Clone, Debug, Default)]
+pub struct AutoAdvance {
+    pub generation: u64,
+    pub loop_mode: Option<LoopMode>,
+}
+
+/// Cycles through the slots from `first_slot_id` up to and including `last_slot_id`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoopMode {
+    pub first_slot_id: u64,
+    pub last_slot_id: u64,
+    pub interval_seconds: u64,
+}
+
+/// Cancels any pending auto-advance, then starts a new one if the selected slide has a duration,
+/// is a countdown, or a loop is running.
+pub fn schedule_auto_advance(
+    state: &mut AppState,
+    app_handle: &tauri::AppHandle,
+) {
+    let auto_advance = &mut state.auto_advance;
+    let selection = &state.session.currently_selected;
+    auto_advance.generation += 1;
+    let scheduled_generation = auto_advance.generation;
+
+    if let Some(countdown) = selection.current_countdown(&state.session.song_list) {
+        start_countdown(app_handle.clone(), countdown.clone(), scheduled_generation);
+    }
+
+    let slide_seconds = selection.current_custom_slide(&state.session.song_list).and_then(|x| x.auto_advance_seconds);
+    match (slide_seconds, &auto_advance.loop_mode) {
+        (Some(seconds), _) => start_slide_timer(app_handle.clone(), seconds, scheduled_generation),
+        (None, Some(loop_mode)) => start_slide_timer(app_handle.clone(), loop_mode.interval_seconds, scheduled_generation),
+        (None, None) => {},
+    }
+}
+
+/// Restarts the timers when a change to the song list replaced or removed the live slot,
+/// so e.g. the countdown of a removed slot stops and a restored countdown ticks again.
+pub fn follow_live_slot(
+    state: &mut AppState,
+    app_handle: &tauri::AppHandle,
+    live_slot_changed: bool,
+) {
+    if live_slot_changed {
+        schedule_auto_advance(state, app_handle);
+    }
+}
+
+/// Goes to the next slide, unless the operator navigated since the advance was scheduled.
+/// Wraps around within the range while a loop is running.
+pub fn advance_if_current(
+    app_handle: &tauri::AppHandle,
+    scheduled_generation: u64,
+) {
+    app_handle.state::<ProgramState>().with_state(|state| {
+        if state.auto_advance.generation != scheduled_generation {
+            return;
+        }
+
+        let selection = &mut state.session.currently_selected;
+        let previous_selection = *selection;
+        match state.auto_advance.loop_mode {
+            Some(ref loop_mode) => selection.next_in_range(&state.session.song_list, loop_mode.first_slot_id, loop_mode.last_slot_id),
+            None => selection.next(&state.session.song_list),
+        }
+
+        if !selection.is_same_slide(&previous_selection) {
+            state.session.emit_selection(&WindowEvents::new(app_handle, &mut state.captions));
+        } else if state.auto_advance.loop_mode.is_none() {
+            // Reached the end of the song list
+            return;
+        }
+
+        schedule_auto_advance(state, app_handle);
+    });
+}
+
+/// Stops a running loop, e.g. when the operator navigates manually.
+pub fn stop_loop_mode(
+    state: &mut AppState,
+    app_handle: &tauri::AppHandle,
+) {
+    if state.auto_advance.loop_mode.take().is_some() {
+        emit_to(app_handle, "main", "update-loop-mode", None::<LoopMode>);
+    }
+}
+
+fn start_slide_timer(
+    app_handle: tauri::AppHandle,
+    seconds: u64,
+    scheduled_generation: u64,
+) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(seconds)).await;
+        advance_if_current(&app_handle, scheduled_generation);
+    });
+}
+
+
+#[tauri::command]
+pub async fn start_loop(
+    first_slot_id: u64,
+    last_slot_id: u64,
+    interval_seconds: u64,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        let song_list = &state.session.song_list;
+        let position = |slot_id: u64| song_list.songs.iter().position(|x| x.id == slot_id);
+        let first_position = position(first_slot_id).ok_or("First slot of the loop not found".to_string())?;
+        let last_position = position(last_slot_id).ok_or("Last slot of the loop not found".to_string())?;
+
+        if first_position > last_position {
+            return Err("The first slot of the loop comes after the last slot".to_string());
+        }
+        if interval_seconds == 0 {
+            return Err("The loop interval must be at least one second".to_string());
+        }
+
+        let loop_mode = LoopMode {
+            first_slot_id,
+            last_slot_id,
+            interval_seconds,
+        };
+        emit_to(&app_handle, "main", "update-loop-mode", Some(&loop_mode));
+        state.auto_advance.loop_mode = Some(loop_mode);
+
+        // Start at the beginning of the range, unless already showing a slot within it
+        let in_range = position(state.session.currently_selected.slot_id())
+            .map(|x| first_position <= x && x <= last_position)
+            .unwrap_or(false);
+        if !in_range {
+            state.session.currently_selected = DisplaySelection::new(&state.session.song_list, first_position, None)?;
+            state.session.emit_selection(&WindowEvents::new(&app_handle, &mut state.captions));
+        }
+
+        schedule_auto_advance(state, &app_handle);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn stop_loop(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        stop_loop_mode(state, &app_handle);
+        // Cancel the pending loop timer
+        state.auto_advance.generation += 1;
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_loop_mode(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<Option<LoopMode>, String> {
+    Ok(program_state.with_state(|state| state.auto_advance.loop_mode.clone()))
+}
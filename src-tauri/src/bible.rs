@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::PathBuf;
+use beamer_core::bible::{passage_to_slides, Bible};
+use beamer_core::bible_import::import_bible_xml;
+use beamer_core::bible_reference::parse_reference;
+use beamer_core::library::file_name_for;
+use beamer_core::song::{BiblePassage, SongList, SongSlotType};
+use crate::{add_slot_to_state, ProgramState};
+
+const BIBLES_DIR: &str = "bibles";
+
+
+#[tauri::command]
+pub async fn import_bible(
+    source_path: PathBuf,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<String>, String> {
+    let xml = fs::read_to_string(&source_path).map_err(|_| "Unable to read bible file".to_string())?;
+    let fallback_name = source_path.file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or("Bible".to_string());
+
+    let bible = import_bible_xml(&xml, &fallback_name)?;
+
+    let bibles_dir = bibles_dir(&app_handle)?;
+    fs::create_dir_all(&bibles_dir).map_err(|_| "Unable to create bibles directory".to_string())?;
+
+    let json = serde_json::to_string(&bible).map_err(|_| "Unable to convert bible to JSON".to_string())?;
+    fs::write(bibles_dir.join(format!("{}.json", file_name_for(&bible.name))), json)
+        .map_err(|_| "Unable to save bible".to_string())?;
+
+    list_bibles(&app_handle)
+}
+
+#[tauri::command]
+pub async fn get_bibles(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<String>, String> {
+    list_bibles(&app_handle)
+}
+
+#[tauri::command]
+pub async fn add_bible_passage(
+    bible_name: &str,
+    reference: &str,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SongList, String> {
+    let bible = load_bible(bible_name, &app_handle)?;
+    let reference = parse_reference(reference)?;
+    let verses = bible.passage(&reference)?;
+
+    let slot = SongSlotType::Bible(BiblePassage {
+        reference: format!("{} {}", bible.book_name(reference.book), reference.range_text()),
+        bible_name: bible.name.clone(),
+        slides: passage_to_slides(&verses),
+    });
+
+    Ok(program_state.with_state(|state| add_slot_to_state(state, &app_handle, slot)))
+}
+
+fn load_bible(bible_name: &str, app_handle: &tauri::AppHandle) -> Result<Bible, String> {
+    let path = bibles_dir(app_handle)?.join(format!("{}.json", file_name_for(bible_name)));
+    let json = fs::read_to_string(path).map_err(|_| format!("Bible '{bible_name}' not found"))?;
+
+    serde_json::from_str(&json).map_err(|_| format!("Bible '{bible_name}' could not be read"))
+}
+
+fn list_bibles(app_handle: &tauri::AppHandle) -> Result<Vec<String>, String> {
+    let entries = match fs::read_dir(bibles_dir(app_handle)?) {
+        Ok(entries) => entries,
+        // Nothing imported yet
+        Err(_) => return Ok(vec![]),
+    };
+
+    let mut names = entries
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .filter(|x| x.extension().map(|extension| extension == "json").unwrap_or(false))
+        .filter_map(|x| x.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect::<Vec<_>>();
+    names.sort();
+
+    Ok(names)
+}
+
+fn bibles_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let mut bibles_dir = app_handle.path_resolver()
+        .app_data_dir()
+        .ok_or("No app data directory found".to_string())?;
+    bibles_dir.push(BIBLES_DIR);
+
+    Ok(bibles_dir)
+}
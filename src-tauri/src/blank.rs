@@ -0,0 +1,54 @@
+use crate::ProgramState;
+use crate::captions::record_blanked;
+use crate::remote::{emit_remote, RemoteEvent};
+use crate::events::emit_to;
+
+/// Hides everything on the presentation except overlays, without changing the `DisplaySelection`.
+/// Shared by the `set_blank` command and the remote API.
+pub fn set_blanked(
+    program_state: &ProgramState,
+    app_handle: &tauri::AppHandle,
+    blank: bool,
+) {
+    program_state.with_state(|state| {
+        state.blank = blank;
+        record_blanked(app_handle, &mut state.captions, blank);
+        emit_blank(app_handle, blank);
+    });
+}
+
+/// Used by OSC controllers with a single blank button.
+pub fn toggle_blanked(
+    program_state: &ProgramState,
+    app_handle: &tauri::AppHandle,
+) {
+    program_state.with_state(|state| {
+        state.blank = !state.blank;
+        record_blanked(app_handle, &mut state.captions, state.blank);
+        emit_blank(app_handle, state.blank);
+    });
+}
+
+fn emit_blank(app_handle: &tauri::AppHandle, blank: bool) {
+    emit_to(app_handle, "presentation", "update-blank", blank);
+    emit_to(app_handle, "main", "update-blank", blank);
+    emit_remote(app_handle, RemoteEvent::Blank(blank));
+}
+
+
+#[tauri::command]
+pub async fn set_blank(
+    blank: bool,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    set_blanked(&program_state, &app_handle, blank);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_blank(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<bool, String> {
+    Ok(program_state.with_state(|state| state.blank))
+}
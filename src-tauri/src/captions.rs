@@ -0,0 +1,148 @@
+use std::convert::Infallible;
+use std::fs;
+use std::path::PathBuf;
+use axum::body::StreamBody;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tokio::sync::broadcast;
+use beamer_core::captions::{to_srt, to_webvtt, webvtt_cue, CaptionCue, CaptionLog};
+use crate::ProgramState;
+use crate::remote::{emit_remote, RemoteEvent};
+use crate::events::emit_to;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum CaptionFormat {
+    Srt,
+    WebVtt,
+}
+
+/// Records the live verse. Called where it changes, so the cue is timed at the change.
+pub fn record_verse(app_handle: &tauri::AppHandle, captions: &mut CaptionLog, verse_lines: Vec<String>) {
+    if let Some(cue) = captions.set_verse(verse_lines) {
+        emit_caption_cue(app_handle, captions, cue);
+    }
+}
+
+pub fn record_blanked(app_handle: &tauri::AppHandle, captions: &mut CaptionLog, blanked: bool) {
+    if let Some(cue) = captions.set_blanked(blanked) {
+        emit_caption_cue(app_handle, captions, cue);
+    }
+}
+
+fn emit_caption_cue(app_handle: &tauri::AppHandle, captions: &CaptionLog, cue: CaptionCue) {
+    emit_to(app_handle, "main", "update-caption-count", captions.cues().len());
+    emit_remote(app_handle, RemoteEvent::CaptionCue(cue));
+}
+
+/// WebVTT cues as they end, relative to the recording start. The stream stays open.
+pub async fn live_webvtt(State(app_handle): State<tauri::AppHandle>) -> impl IntoResponse {
+    let events = app_handle.state::<ProgramState>().remote_events.subscribe();
+
+    let cues = remote_event_stream(events)
+        .filter_map(|event| async move {
+            match event {
+                RemoteEvent::CaptionCue(cue) => Some(webvtt_cue(&cue)),
+                _ => None,
+            }
+        });
+    let body = stream::once(async { "WEBVTT\n\n".to_string() })
+        .chain(cues)
+        .map(Ok::<_, Infallible>);
+
+    ([(header::CONTENT_TYPE, "text/vtt; charset=utf-8")], StreamBody::new(body))
+}
+
+/// The live text, CEA-608 roll-up style: every change sends the visible lines followed by an empty line.
+/// Blanking sends just the empty line, which clears the captions.
+pub async fn live_text(State(app_handle): State<tauri::AppHandle>) -> impl IntoResponse {
+    let events = app_handle.state::<ProgramState>().remote_events.subscribe();
+
+    let body = remote_event_stream(events)
+        .filter_map(|event| async move {
+            match event {
+                RemoteEvent::Verse(verse) => {
+                    let lines = verse.lines.iter()
+                        .map(|x| x.trim())
+                        .filter(|x| !x.is_empty())
+                        .collect::<Vec<_>>();
+                    if lines.is_empty() {
+                        Some("\n".to_string())
+                    } else {
+                        Some(format!("{}\n\n", lines.join("\n")))
+                    }
+                },
+                RemoteEvent::Blank(true) => Some("\n".to_string()),
+                _ => None,
+            }
+        })
+        .map(Ok::<_, Infallible>);
+
+    ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], StreamBody::new(body))
+}
+
+fn remote_event_stream(receiver: broadcast::Receiver<RemoteEvent>) -> impl futures_util::Stream<Item = RemoteEvent> {
+    stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+
+/// Sets the recording start marker: captions are timed from now. Clears earlier captions.
+#[tauri::command]
+pub async fn start_caption_recording(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        let verse_lines = state.session.currently_selected.current_verse(&state.session.song_list).lines;
+        state.captions.start_recording(verse_lines, state.blank);
+    });
+    emit_to(&app_handle, "main", "update-caption-recording", true);
+    emit_to(&app_handle, "main", "update-caption-count", 0);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_caption_recording(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        if let Some(cue) = state.captions.stop_recording() {
+            emit_caption_cue(&app_handle, &state.captions, cue);
+        }
+    });
+    emit_to(&app_handle, "main", "update-caption-recording", false);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_caption_status(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(bool, usize), String> { // Ok(recording, number of cues)
+    Ok(program_state.with_state(|state| (state.captions.is_recording(), state.captions.cues().len())))
+}
+
+/// Writes the cues so far. The cue that is still live is not included.
+#[tauri::command]
+pub async fn export_captions(
+    format: CaptionFormat,
+    target_path: PathBuf,
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(), String> {
+    let text = program_state.with_state(|state| match format {
+        CaptionFormat::Srt => to_srt(state.captions.cues()),
+        CaptionFormat::WebVtt => to_webvtt(state.captions.cues()),
+    });
+    fs::write(target_path, text).map_err(|_| "Unable to write captions".to_string())
+}
@@ -1,14 +1,49 @@
 use std::fs;
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
-use tauri::Manager;
 use crate::ProgramState;
+use crate::headless::HeadlessConfig;
+use crate::logging::{add_secret, LogConfig, REDACTED};
+use crate::obs::ObsStyle;
+use crate::obs_websocket::ObsWebSocketConfig;
+use crate::osc::OscConfig;
+use crate::remote::RemoteConfig;
+use crate::events::emit_to;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ProgramConfig {
     pub config_path: PathBuf,
     pub genius_api_token: Option<String>,
     pub font_size: String,
+    #[serde(default)]
+    pub remote: RemoteConfig,
+    #[serde(default)]
+    pub osc: OscConfig,
+    #[serde(default)]
+    pub obs: ObsStyle,
+    #[serde(default)]
+    pub obs_websocket: ObsWebSocketConfig,
+    #[serde(default)]
+    pub headless: HeadlessConfig,
+    #[serde(default)]
+    pub log: LogConfig,
+}
+
+/// The config without tokens and passwords, e.g. for logging and diagnostics.
+pub fn sanitized_config(config: &ProgramConfig) -> ProgramConfig {
+    let mut config = config.clone();
+
+    if config.genius_api_token.is_some() {
+        config.genius_api_token = Some(REDACTED.to_string());
+    }
+    for device in config.remote.devices.iter_mut() {
+        device.token = REDACTED.to_string();
+    }
+    if config.obs_websocket.password.is_some() {
+        config.obs_websocket.password = Some(REDACTED.to_string());
+    }
+
+    config
 }
 
 
@@ -16,18 +51,14 @@ pub struct ProgramConfig {
 pub async fn get_genius_token(
     program_state: tauri::State<'_, ProgramState>,
 ) -> Result<String, ()> {
-    let config = program_state.config.read().await;
-
-    Ok((*config).genius_api_token.clone().unwrap_or("".to_string()))
+    Ok(program_state.with_state(|state| state.config.genius_api_token.clone().unwrap_or("".to_string())))
 }
 
 #[tauri::command]
 pub async fn get_font_size(
     program_state: tauri::State<'_, ProgramState>,
 ) -> Result<String, ()> {
-    let config = program_state.config.read().await;
-
-    Ok((*config).font_size.clone())
+    Ok(program_state.with_state(|state| state.config.font_size.clone()))
 }
 
 #[tauri::command]
@@ -35,9 +66,8 @@ pub async fn set_genius_token(
     new_token: String,
     program_state: tauri::State<'_, ProgramState>,
 ) -> Result<(), ()> {
-    let mut config = program_state.config.write().await;
-
-    config.genius_api_token = Some(new_token);
+    add_secret(&new_token);
+    program_state.with_state(|state| state.config.genius_api_token = Some(new_token));
 
     Ok(())
 }
@@ -48,10 +78,34 @@ pub async fn set_font_size(
     program_state: tauri::State<'_, ProgramState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), ()> {
-    let mut config = program_state.config.write().await;
-    config.font_size = new_font_size.clone();
+    program_state.with_state(|state| state.config.font_size = new_font_size.clone());
+
+    emit_to(&app_handle, "presentation", "update-font-size", new_font_size);
+    Ok(())
+}
+
+/// The settings the remote server is running with, which can differ from the config
+/// when it was started with `--remote-port` or in headless mode.
+#[tauri::command]
+pub async fn get_remote_config(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<RemoteConfig, ()> {
+    Ok(program_state.with_state(|state| state.remote.clone()))
+}
+
+/// Takes effect after restarting the app. Paired devices are managed separately.
+#[tauri::command]
+pub async fn set_remote_config(
+    new_remote_config: RemoteConfig,
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(), ()> {
+    program_state.with_state(|state| {
+        state.config.remote = RemoteConfig {
+            devices: state.config.remote.devices.clone(),
+            ..new_remote_config
+        };
+    });
 
-    app_handle.emit_to("presentation", "update-font-size", new_font_size).expect("could not emit update-font-size");
     Ok(())
 }
 
@@ -59,14 +113,17 @@ pub async fn set_font_size(
 pub async fn save_config(
     program_state: tauri::State<'_, ProgramState>,
 ) -> Result<(), String> {
-    let config = program_state.config.read().await;
+    let config = program_state.with_state(|state| state.config.clone());
 
-    let json = serde_json::to_string(&*config).map_err(|x| "Unable to parse JSON".to_string())?;
-    // fs::write(config.config_path.clone(), json).map_err(|x| "Unable to write to config".to_string())?;
-    let new_path = config.config_path.clone();
-    fs::create_dir_all(config.config_path.parent().unwrap()).unwrap();
-    fs::write(new_path, json).unwrap();
+    write_config(&config)
+}
+
+pub fn write_config(config: &ProgramConfig) -> Result<(), String> {
+    let json = serde_json::to_string(config).map_err(|_| "Unable to parse JSON".to_string())?;
+    if let Some(config_dir) = config.config_path.parent() {
+        fs::create_dir_all(config_dir).map_err(|_| "Unable to create config directory".to_string())?;
+    }
+    fs::write(&config.config_path, json).map_err(|_| "Unable to write to config".to_string())?;
 
     Ok(())
 }
-
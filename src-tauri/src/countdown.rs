@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+use chrono::Local;
+use serde::Serialize;
+use tauri::Manager;
+use beamer_core::song::{Countdown, CountdownEndAction, CountdownTarget};
+use crate::ProgramState;
+use crate::auto_advance::advance_if_current;
+use crate::events::emit_to;
+
+/// Sent every second to the presentation, `None` when no countdown is live.
+#[derive(Clone, Debug, Serialize)]
+pub struct CountdownUpdate {
+    pub label: String,
+    pub remaining_seconds: u64,
+}
+
+/// Ticks in the backend, so the time shown does not depend on timers in the webview.
+/// Stops as soon as the selection changes.
+pub fn start_countdown(
+    app_handle: tauri::AppHandle,
+    countdown: Countdown,
+    scheduled_generation: u64,
+) {
+    tauri::async_runtime::spawn(async move {
+        let end = countdown_end(&countdown.target);
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+        loop {
+            interval.tick().await;
+
+            let remaining = end.saturating_duration_since(Instant::now());
+            // Round up: show 0 only when the time is actually up
+            let remaining_seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+
+            let generation = app_handle.state::<ProgramState>().with_state(|state| state.auto_advance.generation);
+            if generation != scheduled_generation {
+                return;
+            }
+
+            emit_to(&app_handle, "presentation", "update-countdown", Some(CountdownUpdate {
+                label: countdown.label.clone(),
+                remaining_seconds,
+            }));
+
+            if remaining_seconds == 0 {
+                if let CountdownEndAction::AdvanceToNextSlot = countdown.end_action {
+                    advance_if_current(&app_handle, scheduled_generation);
+                }
+                return;
+            }
+        }
+    });
+}
+
+fn countdown_end(target: &CountdownTarget) -> Instant {
+    let now = Instant::now();
+
+    match *target {
+        CountdownTarget::Duration { seconds } => now + Duration::from_secs(seconds),
+        CountdownTarget::WallClock { hour, minute } => {
+            let local_now = Local::now();
+
+            // A time that has already passed today counts down to zero immediately
+            let remaining = local_now.date_naive()
+                .and_hms_opt(hour, minute, 0)
+                .and_then(|x| x.and_local_timezone(Local).earliest())
+                .and_then(|x| (x - local_now).to_std().ok())
+                .unwrap_or_default();
+
+            now + remaining
+        },
+    }
+}
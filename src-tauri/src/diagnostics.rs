@@ -0,0 +1,67 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tar::{Builder, Header};
+use crate::ProgramState;
+use crate::config::sanitized_config;
+use crate::logging::{log_files, redact};
+
+/// Writes a `.tar.gz` with the logs, the config without secrets and the version, to attach to a bug report.
+#[tauri::command]
+pub async fn export_diagnostics(
+    target_path: PathBuf,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    log::logger().flush();
+
+    let config = program_state.with_state(|state| state.config.clone());
+    let package_info = app_handle.package_info();
+    let version = format!(
+        "{} {}\n{} {}\n",
+        package_info.name,
+        package_info.version,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+    );
+    let config_json = serde_json::to_string_pretty(&sanitized_config(&config))
+        .map_err(|_| "Unable to convert config to JSON".to_string())?;
+
+    let file = File::create(&target_path).map_err(|_| format!("Unable to create {}", target_path.display()))?;
+    let mut archive = Builder::new(GzEncoder::new(file, Compression::default()));
+
+    append_file(&mut archive, "version.txt", version.as_bytes())?;
+    append_file(&mut archive, "config.json", config_json.as_bytes())?;
+
+    if let Some(log_dir) = app_handle.path_resolver().app_log_dir() {
+        for path in log_files(&log_dir, &config.log) {
+            // Older logs may have been written before a secret was known
+            let log = match fs::read(&path) {
+                Ok(log) => redact(&String::from_utf8_lossy(&log)),
+                Err(error) => {
+                    log::warn!("could not read {}: {error}", path.display());
+                    continue;
+                },
+            };
+            append_file(&mut archive, &Path::new("logs").join(path.file_name().unwrap_or_default()), log.as_bytes())?;
+        }
+    }
+
+    archive.into_inner()
+        .and_then(|x| x.finish())
+        .map_err(|_| format!("Unable to write {}", target_path.display()))?;
+
+    log::info!("exported diagnostics to {}", target_path.display());
+    Ok(())
+}
+
+fn append_file(archive: &mut Builder<GzEncoder<File>>, path: impl AsRef<Path>, contents: &[u8]) -> Result<(), String> {
+    let mut header = Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+
+    archive.append_data(&mut header, path, contents)
+        .map_err(|_| "Unable to write the diagnostics archive".to_string())
+}
@@ -1,129 +1,58 @@
-use std::cmp::{max, min};
-use std::vec;
-use serde::Serialize;
-use tauri::Manager;
-use crate::ProgramState;
-use crate::song::{Song, SongList, SongSlotType, Verse};
-
-/// For tracking which verse to currently show
-
-#[derive(Clone, Debug, Serialize)]
-pub struct DisplaySelection {
-    slot_id: u64,
-    slot_position: usize,
-    verse_num: usize,
-    song: Song,
+use beamer_core::display_selection::{NavigationError, SelectionUpdate};
+use crate::{AppState, ProgramState};
+use crate::auto_advance::{schedule_auto_advance, stop_loop_mode};
+use crate::events::WindowEvents;
+
+/// Starts the timers of the new selection, after navigating manually.
+fn selection_changed(
+    state: &mut AppState,
+    app_handle: &tauri::AppHandle,
+) {
+    stop_loop_mode(state, app_handle);
+    schedule_auto_advance(state, app_handle);
 }
 
-impl DisplaySelection {
-    pub fn new(song_list: &SongList, song_position: usize, verse_num: Option<usize>) -> Self {
-        let verse_num = verse_num.unwrap_or(0);
-
-        let song_slot = &song_list.songs[song_position];
-        let slot_id = song_slot.id;
-
-        let song = Self::unwrap_or_song(&song_slot.slot);
-
-        if song.verses.get(verse_num).is_none() {
-            panic!("verse_num is {}, but song is of length {}", verse_num, song.verses.len());
-        }
-
-        Self {
-            slot_id,
-            slot_position: song_position,
-            verse_num,
-            song,
-        }
-    }
-
-    pub fn current_verse(&self) -> &Verse {
-        &self.song.verses[self.verse_num]
-    }
-
-    pub fn previous(&mut self, song_list: &SongList) {
-        if self.verse_num > 0 {
-            self.verse_num -= 1;
-        } else {
-            let current_position = song_list.songs.iter().position(|x| x.id == self.slot_id);
-
-            // saturating sub: prevent underflow
-            let new_position = current_position.map(|x| x.saturating_sub(1)).unwrap_or(self.slot_position);
-
-            if new_position >= 0 && new_position < song_list.songs.len() {
-                let next_song = song_list.songs[new_position].clone();
-                let verse_num = match next_song.slot {
-                    SongSlotType::Empty => 0,
-                    SongSlotType::Song(ref song) => song.num_verses() - 1,
-                };
-
-                self.slot_id = next_song.id;
-                self.slot_position = new_position;
-                self.verse_num = verse_num;
-                self.song = Self::unwrap_or_song(&next_song.slot);
-
-            } else {
-                if let Some(next_song) = song_list.songs.last() {
-                    self.slot_id = next_song.id;
-                    self.slot_position = song_list.songs.len() - 1;
-                    self.verse_num = 0;
-                    self.song = Self::unwrap_or_song(&next_song.slot);
-
-                } else {
-                    self.slot_id = 0;
-                    self.slot_position = 0;
-                    self.verse_num = 0;
-                    self.song = Self::unwrap_or_song(&SongSlotType::Empty);
-                }
-            }
-        }
-    }
-
-    pub fn next(&mut self, song_list: &SongList) {
-        if self.verse_num < self.song.verses.len().saturating_sub(1) {
-            // Next verse in same song available: Go there
-            self.verse_num += 1;
-        } else {
-            // Go to next song
-            let current_position = song_list.songs.iter().position(|x| x.id == self.slot_id);
-
-            // If song removed: Go to song that is now at that position
-            let song_list_len = song_list.songs.len();
-            let new_position= current_position.map(|x| x + 1).unwrap_or(self.slot_position);
-
-            if new_position < song_list.songs.len() {
-                // Go to next if not at end
-                let next_song = song_list.songs[new_position].clone();
-
-                self.slot_id = next_song.id;
-                self.slot_position = new_position;
-                self.verse_num = 0;
-                self.song = Self::unwrap_or_song(&next_song.slot);
-            } else {
-                // Or if index more than songlist length: Go to last song
-                // If Songlist empty: Go to empty song slot with id 0
+/// Shared by the `next_verse` command and the remote API.
+pub fn select_next(
+    program_state: &ProgramState,
+    app_handle: &tauri::AppHandle,
+) {
+    program_state.with_state(|state| {
+        state.session.select_next(&WindowEvents::new(app_handle, &mut state.captions));
+        selection_changed(state, app_handle);
+    });
+}
 
-                if let Some(next_song) = song_list.songs.last() {
-                    self.slot_id = next_song.id;
-                    self.slot_position = song_list.songs.len() - 1;
-                    self.verse_num = next_song.slot.num_verses().saturating_sub(1);
-                    self.song = Self::unwrap_or_song(&next_song.slot);
+/// Shared by the `previous_verse` command and the remote API.
+pub fn select_previous(
+    program_state: &ProgramState,
+    app_handle: &tauri::AppHandle,
+) {
+    program_state.with_state(|state| {
+        state.session.select_previous(&WindowEvents::new(app_handle, &mut state.captions));
+        selection_changed(state, app_handle);
+    });
+}
 
-                } else {
-                    self.slot_id = 0;
-                    self.slot_position = 0;
-                    self.verse_num = 0;
-                    self.song = Self::unwrap_or_song(&SongSlotType::Empty);
-                }
-            }
-        }
-    }
+/// Jumps to a verse of the slot with `slot_id`. Shared by the `go_to_slot` command and the remote API.
+pub fn select_slot(
+    program_state: &ProgramState,
+    app_handle: &tauri::AppHandle,
+    slot_id: u64,
+    verse_num: Option<usize>,
+) -> Result<(), NavigationError> {
+    program_state.with_state(|state| {
+        state.session.select_slot(&WindowEvents::new(app_handle, &mut state.captions), slot_id, verse_num)?;
+        selection_changed(state, app_handle);
+        Ok(())
+    })
+}
 
-    pub fn unwrap_or_song(slot_type: &SongSlotType) -> Song {
-        match slot_type {
-            SongSlotType::Empty => Song::new("Empty Panel", "None", vec![Verse::new(vec![])]),
-            SongSlotType::Song(song) => song.clone(),
-        }
-    }
+/// The current and next selection, with the version of the last update.
+pub fn display_selections(
+    program_state: &ProgramState,
+) -> SelectionUpdate {
+    program_state.with_state(|state| state.session.selection_update())
 }
 
 
@@ -132,19 +61,7 @@ pub async fn next_verse(
     program_state: tauri::State<'_, ProgramState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    use tauri::Manager;
-
-    let song_list = program_state.song_list.read().await;
-    let mut selection = program_state.currently_selected.write().await;
-
-    selection.next(&song_list);
-    app_handle.emit_to("presentation", "update-verse", selection.current_verse()).expect("could not emit update-verse");
-
-    let display_selection = selection.clone();
-    let mut next_display_selection= selection.clone();
-    next_display_selection.next(&song_list);
-
-    app_handle.emit_to("main", "update-display-selection", (display_selection, next_display_selection)).expect("could not emit update-display-selection");
+    select_next(&program_state, &app_handle);
     Ok(())
 }
 
@@ -153,32 +70,23 @@ pub async fn previous_verse(
     program_state: tauri::State<'_, ProgramState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    use tauri::Manager;
-
-    let song_list = program_state.song_list.read().await;
-    let mut selection = program_state.currently_selected.write().await;
-
-    selection.previous(&song_list);
-    app_handle.emit_to("presentation", "update-verse", selection.current_verse()).expect("could not emit update-verse");
-
-    let mut next_display_selection = selection.clone();
-    let display_selection = selection.clone();
-    next_display_selection.next(&song_list);
-
-    app_handle.emit_to("main", "update-display-selection", (display_selection, next_display_selection)).expect("could not emit update-display-selection");
+    select_previous(&program_state, &app_handle);
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_display_selection(
+pub async fn go_to_slot(
+    slot_id: u64,
+    verse_num: Option<usize>,
     program_state: tauri::State<'_, ProgramState>,
-) -> Result<(DisplaySelection, DisplaySelection), String> {
-    let song_list = program_state.song_list.read().await;
-    let selection = program_state.currently_selected.read().await;
-
-    let display_selection = selection.clone();
-    let mut next_display_selection = display_selection.clone();
-    next_display_selection.next(&song_list);
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    Ok(select_slot(&program_state, &app_handle, slot_id, verse_num)?)
+}
 
-    Ok((display_selection, next_display_selection))
+#[tauri::command]
+pub async fn get_display_selection(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<SelectionUpdate, String> {
+    Ok(display_selections(&program_state))
 }
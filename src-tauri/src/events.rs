@@ -0,0 +1,71 @@
+use std::cell::RefCell;
+use serde::Serialize;
+use tauri::Manager;
+use beamer_core::captions::CaptionLog;
+use beamer_core::display_selection::{DisplaySelection, SelectionUpdate};
+use beamer_core::events::EventSink;
+use beamer_core::history::HistoryLabels;
+use beamer_core::setlist::SongListUpdate;
+use beamer_core::song::SongList;
+use crate::captions::record_verse;
+use crate::countdown::CountdownUpdate;
+use crate::remote::{emit_remote, RemoteEvent};
+use crate::stage::StageDisplay;
+
+/// Sends an event to one window. A window that can't be reached, e.g. because it was closed,
+/// must not take down the app during a service, so failures are only logged.
+pub fn emit_to<S: Serialize + Clone>(app_handle: &tauri::AppHandle, window: &str, event: &str, payload: S) {
+    if let Err(error) = app_handle.emit_to(window, event, payload) {
+        log::warn!("could not emit {event} to {window}: {error}");
+    }
+}
+
+/// Passes the events of the session on to the windows and remote clients.
+/// Also records the live slides in the captions, at the moment they change.
+pub struct WindowEvents<'a> {
+    app_handle: &'a tauri::AppHandle,
+    captions: RefCell<&'a mut CaptionLog>,
+}
+
+impl<'a> WindowEvents<'a> {
+    pub fn new(app_handle: &'a tauri::AppHandle, captions: &'a mut CaptionLog) -> Self {
+        Self {
+            app_handle,
+            captions: RefCell::new(captions),
+        }
+    }
+}
+
+impl EventSink for WindowEvents<'_> {
+    fn song_list_changed(&self, update: &SongListUpdate) {
+        emit_to(self.app_handle, "main", "update-song-list", update);
+        emit_remote(self.app_handle, RemoteEvent::SongList(update.clone()));
+    }
+
+    fn history_changed(&self, labels: &HistoryLabels) {
+        emit_to(self.app_handle, "main", "update-history", labels);
+    }
+
+    fn selection_changed(&self, update: &SelectionUpdate) {
+        emit_to(self.app_handle, "main", "update-display-selection", update);
+        emit_remote(self.app_handle, RemoteEvent::Selection(Box::new(update.clone())));
+    }
+
+    /// Updates the presentation and stage windows.
+    fn live_slide_changed(&self, song_list: &SongList, selection: &DisplaySelection) {
+        let verse = selection.current_verse(song_list);
+        emit_to(self.app_handle, "presentation", "update-verse", &verse);
+        emit_to(self.app_handle, "presentation", "update-image", selection.current_image(song_list));
+        emit_to(self.app_handle, "presentation", "update-custom-slide", selection.current_custom_slide(song_list));
+        // A live countdown sends its own updates every second
+        if selection.current_countdown(song_list).is_none() {
+            emit_to(self.app_handle, "presentation", "update-countdown", None::<CountdownUpdate>);
+        }
+        record_verse(self.app_handle, &mut self.captions.borrow_mut(), verse.lines.clone());
+        emit_remote(self.app_handle, RemoteEvent::Verse(verse));
+
+        let mut next_display_selection = *selection;
+        next_display_selection.next(song_list);
+        emit_to(self.app_handle, "stage", "update-stage-display", StageDisplay::new(song_list, selection, &next_display_selection));
+    }
+}
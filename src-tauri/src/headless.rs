@@ -0,0 +1,205 @@
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use tauri::{WindowBuilder, WindowUrl};
+use crate::config::ProgramConfig;
+use crate::remote::RemoteConfig;
+use crate::remote_qr::remote_url;
+
+/// Next to the config, for when the console can't be seen, e.g. when started as a service.
+const PIN_FILE: &str = "remote-pin.txt";
+
+pub const USAGE: &str = "\
+Options:
+  --headless, --no-headless          Run without the main window, driven through the remote API
+  --presentation, --no-presentation  Open the presentation window
+  --stage, --no-stage                Open the stage display window
+  --remote-port <port>               Serve the remote API, OBS page and captions on this port";
+
+/// Running without the main window, e.g. on a stage computer that is only driven remotely.
+/// The remote API is always served when headless, it is the only way to control the app.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeadlessConfig {
+    pub enabled: bool,
+    pub presentation_window: bool,
+    pub stage_window: bool,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            presentation_window: true,
+            stage_window: true,
+        }
+    }
+}
+
+/// Flags that take precedence over the config file. They are not saved in the config.
+#[derive(Clone, Debug, Default)]
+pub struct CommandLine {
+    pub headless: Option<bool>,
+    pub presentation_window: Option<bool>,
+    pub stage_window: Option<bool>,
+    pub remote_port: Option<u16>,
+    /// Logged once logging is set up.
+    pub unknown_args: Vec<String>,
+}
+
+impl CommandLine {
+    /// Unknown arguments are skipped, the OS may add its own.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut command_line = Self::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => command_line.headless = Some(true),
+                "--no-headless" => command_line.headless = Some(false),
+                "--presentation" => command_line.presentation_window = Some(true),
+                "--no-presentation" => command_line.presentation_window = Some(false),
+                "--stage" => command_line.stage_window = Some(true),
+                "--no-stage" => command_line.stage_window = Some(false),
+                "--remote-port" => {
+                    let port = args.next()
+                        .and_then(|x| x.parse().ok())
+                        .ok_or("--remote-port needs a port number".to_string())?;
+                    command_line.remote_port = Some(port);
+                },
+                _ => command_line.unknown_args.push(arg),
+            }
+        }
+
+        Ok(command_line)
+    }
+
+    pub fn headless_config(&self, config: &ProgramConfig) -> HeadlessConfig {
+        HeadlessConfig {
+            enabled: self.headless.unwrap_or(config.headless.enabled),
+            presentation_window: self.presentation_window.unwrap_or(config.headless.presentation_window),
+            stage_window: self.stage_window.unwrap_or(config.headless.stage_window),
+        }
+    }
+
+    /// The remote server config to start with.
+    pub fn remote_config(&self, config: &ProgramConfig, headless: &HeadlessConfig) -> RemoteConfig {
+        let mut remote_config = config.remote.clone();
+        if headless.enabled {
+            remote_config.enabled = true;
+        }
+        if let Some(port) = self.remote_port {
+            remote_config.port = port;
+        }
+
+        remote_config
+    }
+}
+
+/// Opens the windows, leaving out the main window when headless.
+pub fn open_windows(app: &tauri::App, headless: &HeadlessConfig) -> tauri::Result<()> {
+    if !headless.enabled {
+        WindowBuilder::new(app, "main", WindowUrl::App("index.html".into()))
+            .title("Main Screen")
+            .inner_size(1200.0, 800.0)
+            .resizable(true)
+            .build()?;
+    }
+
+    if headless.presentation_window {
+        WindowBuilder::new(app, "presentation", WindowUrl::App("presentation.html".into()))
+            .title("Presentation Screen")
+            .inner_size(1400.0, 900.0)
+            .build()?;
+    }
+
+    if headless.stage_window {
+        WindowBuilder::new(app, "stage", WindowUrl::App("stage.html".into()))
+            .title("Stage Display")
+            .inner_size(1400.0, 900.0)
+            .build()?;
+    }
+
+    Ok(())
+}
+
+/// Release builds on Windows start without a console, so output is lost unless
+/// the console of e.g. the terminal the app was started from is attached.
+/// Does nothing when there is no such console.
+#[cfg(windows)]
+pub fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // Fails when there is no parent console, or one is already attached: both are fine
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
+
+/// Without the main window, the console is the only place to show how to pair a remote.
+pub fn print_pairing(remote_config: &RemoteConfig, config_path: &Path, pin: &str) {
+    match remote_url(remote_config) {
+        Ok(url) => log::info!("remote at {url}"),
+        Err(error) => log::info!("remote on port {}, {error}", remote_config.port),
+    }
+    show_pin(config_path, pin);
+}
+
+/// Prints the PIN and writes it to a file next to the config.
+/// Not logged: the log ends up in diagnostics bundles while the PIN is still valid.
+pub fn show_pin(config_path: &Path, pin: &str) {
+    println!("Pair a remote with PIN {pin}");
+
+    let pin_path = config_path.with_file_name(PIN_FILE);
+    match fs::write(&pin_path, format!("{pin}\n")) {
+        Ok(()) => log::info!("wrote the remote PIN to {}", pin_path.display()),
+        Err(error) => log::warn!("could not write the remote PIN to {}: {error}", pin_path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CommandLine, String> {
+        CommandLine::parse(args.iter().map(|x| x.to_string()))
+    }
+
+    #[test]
+    fn parse_reads_every_flag() {
+        let command_line = parse(&["--headless", "--no-presentation", "--stage", "--remote-port", "8080"]).unwrap();
+
+        assert_eq!(command_line.headless, Some(true));
+        assert_eq!(command_line.presentation_window, Some(false));
+        assert_eq!(command_line.stage_window, Some(true));
+        assert_eq!(command_line.remote_port, Some(8080));
+        assert!(command_line.unknown_args.is_empty());
+    }
+
+    #[test]
+    fn parse_lets_later_flags_win_and_leaves_out_missing_ones() {
+        let command_line = parse(&["--headless", "--no-headless", "--no-stage"]).unwrap();
+
+        assert_eq!(command_line.headless, Some(false));
+        assert_eq!(command_line.presentation_window, None);
+        assert_eq!(command_line.stage_window, Some(false));
+        assert_eq!(command_line.remote_port, None);
+    }
+
+    #[test]
+    fn parse_keeps_unknown_arguments() {
+        let command_line = parse(&["-psn_0_12345", "--headless", "song-list.json"]).unwrap();
+
+        assert_eq!(command_line.headless, Some(true));
+        assert_eq!(command_line.unknown_args, vec!["-psn_0_12345", "song-list.json"]);
+    }
+
+    #[test]
+    fn parse_rejects_a_missing_or_invalid_port() {
+        assert!(parse(&["--remote-port"]).is_err());
+        assert!(parse(&["--remote-port", "not-a-port"]).is_err());
+        assert!(parse(&["--remote-port", "70000"]).is_err());
+        assert!(parse(&["--remote-port", "--headless"]).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use beamer_core::history::HistoryLabels;
+use beamer_core::song::SongList;
+use crate::ProgramState;
+use crate::auto_advance::follow_live_slot;
+use crate::events::WindowEvents;
+
+#[tauri::command]
+pub async fn undo(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SongList, String> {
+    program_state.with_state(|state| {
+        let live_slot_changed = state.session.undo(&WindowEvents::new(&app_handle, &mut state.captions))?;
+        follow_live_slot(state, &app_handle, live_slot_changed);
+        Ok(state.session.song_list.clone())
+    })
+}
+
+#[tauri::command]
+pub async fn redo(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SongList, String> {
+    program_state.with_state(|state| {
+        let live_slot_changed = state.session.redo(&WindowEvents::new(&app_handle, &mut state.captions))?;
+        follow_live_slot(state, &app_handle, live_slot_changed);
+        Ok(state.session.song_list.clone())
+    })
+}
+
+#[tauri::command]
+pub async fn get_history(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<HistoryLabels, String> {
+    Ok(program_state.with_state(|state| state.session.history.labels()))
+}
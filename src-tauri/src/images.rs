@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use beamer_core::song::{ImageScaling, ImageSlide, SongList, SongSlotType};
+use crate::{add_slot_to_state, ProgramState};
+
+const IMAGES_DIR: &str = "images";
+
+/// Copies the image into the app data dir, so the song list does not depend on the original file.
+#[tauri::command]
+pub async fn add_image(
+    source_path: PathBuf,
+    title: &str,
+    scaling: ImageScaling,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SongList, String> {
+    let path = copy_to_images_dir(&source_path, &app_handle)?;
+
+    let title = if title.is_empty() {
+        source_path.file_stem().map(|x| x.to_string_lossy().into_owned()).unwrap_or_default()
+    } else {
+        title.to_string()
+    };
+
+    let slot = SongSlotType::Image(ImageSlide {
+        title,
+        path,
+        scaling,
+    });
+
+    Ok(program_state.with_state(|state| add_slot_to_state(state, &app_handle, slot)))
+}
+
+fn copy_to_images_dir(source_path: &Path, app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let mut images_dir = app_handle.path_resolver()
+        .app_data_dir()
+        .ok_or("No app data directory found".to_string())?;
+    images_dir.push(IMAGES_DIR);
+    fs::create_dir_all(&images_dir).map_err(|_| "Unable to create images directory".to_string())?;
+
+    let file_name = source_path.file_name()
+        .ok_or("Invalid image path".to_string())?
+        .to_string_lossy();
+
+    // Prefix with a timestamp: the same file name may be added more than once
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_millis())
+        .unwrap_or_default();
+    let target_path = images_dir.join(format!("{timestamp}-{file_name}"));
+
+    fs::copy(source_path, &target_path).map_err(|_| "Unable to copy image".to_string())?;
+
+    Ok(target_path)
+}
@@ -0,0 +1,46 @@
+use beamer_core::library::{Library, LIBRARY_DIR};
+use beamer_core::song::{Song, SongList, SongSlotType};
+use crate::{add_slot_to_state, ProgramState};
+
+/// The library in the app data dir, the same one the `beamer` command line tool uses.
+pub fn library(app_handle: &tauri::AppHandle) -> Result<Library, String> {
+    let mut library_dir = app_handle.path_resolver()
+        .app_data_dir()
+        .ok_or("No app data directory found".to_string())?;
+    library_dir.push(LIBRARY_DIR);
+
+    Ok(Library::new(library_dir))
+}
+
+/// Keeps an added or edited song for later services.
+/// The song is already in the song list, so a failure is only logged.
+pub fn save_to_library(app_handle: &tauri::AppHandle, song: &Song) {
+    if let Err(error) = library(app_handle).and_then(|library| library.add(song)) {
+        log::warn!("could not save '{}' to the library: {error}", song.title);
+    }
+}
+
+
+/// Songs with `query` in the title, author or lyrics. All songs for an empty query.
+#[tauri::command]
+pub async fn search_library(
+    query: &str,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<Song>, String> {
+    let library = library(&app_handle)?;
+
+    if query.trim().is_empty() {
+        library.songs()
+    } else {
+        library.search(query.trim())
+    }
+}
+
+#[tauri::command]
+pub async fn add_library_song(
+    song: Song,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SongList, String> {
+    Ok(program_state.with_state(|state| add_slot_to_state(state, &app_handle, SongSlotType::Song(song))))
+}
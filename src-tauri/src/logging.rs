@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError, RwLock};
+use chrono::Local;
+use lazy_static::lazy_static;
+use log::{LevelFilter, Log, Metadata, Record};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::config::ProgramConfig;
+
+const LOG_FILE_NAME: &str = "beamer.log";
+/// Overrides the levels from the config, e.g. `BEAMER_LOG=info,app::remote=debug`.
+const LOG_ENV_VAR: &str = "BEAMER_LOG";
+/// Shorter values are too common to replace everywhere.
+const MIN_SECRET_LENGTH: usize = 4;
+
+pub const REDACTED: &str = "[redacted]";
+
+/// Which messages are logged, and how much of the log is kept.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogConfig {
+    /// `off`, `error`, `warn`, `info`, `debug` or `trace`.
+    pub level: String,
+    /// Levels for single modules and their submodules, e.g. `"app::osc": "debug"`.
+    #[serde(default)]
+    pub modules: HashMap<String, String>,
+    /// The log file is rotated when it would grow beyond this size.
+    pub max_file_size_kb: u64,
+    /// How many rotated files are kept besides the current one.
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: HashMap::new(),
+            max_file_size_kb: 1024,
+            max_files: 5,
+        }
+    }
+}
+
+lazy_static! {
+    /// Tokens and passwords that are currently in use, replaced wherever they show up in a message.
+    static ref SECRETS: RwLock<Vec<String>> = RwLock::new(vec![]);
+}
+
+/// Makes sure `secret` never ends up in the log, also when it is part of e.g. an error message.
+pub fn add_secret(secret: &str) {
+    if secret.len() < MIN_SECRET_LENGTH {
+        return;
+    }
+
+    let mut secrets = SECRETS.write().unwrap_or_else(PoisonError::into_inner);
+    if !secrets.iter().any(|x| x == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// For secrets that stopped working, e.g. a used pairing token, so the list does not keep growing.
+pub fn remove_secret(secret: &str) {
+    SECRETS.write().unwrap_or_else(PoisonError::into_inner).retain(|x| x != secret);
+}
+
+pub fn add_config_secrets(config: &ProgramConfig) {
+    if let Some(ref token) = config.genius_api_token {
+        add_secret(token);
+    }
+    for device in &config.remote.devices {
+        add_secret(&device.token);
+    }
+    if let Some(ref password) = config.obs_websocket.password {
+        add_secret(password);
+    }
+}
+
+/// Replaces known secrets, and anything that looks like a token or password, with `[redacted]`.
+pub fn redact(text: &str) -> String {
+    lazy_static! {
+        static ref BEARER_REGEX: Regex = Regex::new(r#"(?i)\b(bearer\s+)[^\s"',]+"#).unwrap();
+        // `?token=...` in URLs
+        static ref QUERY_REGEX: Regex = Regex::new(r#"(?i)\b((?:[a-z_]*token|password|secret|api_key|pin)=)[^&\s"']+"#).unwrap();
+        // `"token": "..."` in JSON and `token: Some("...")` in debug output
+        static ref FIELD_REGEX: Regex = Regex::new(r#"(?i)\b((?:[a-z_]*token|password|secret|api_key|pin)"?\s*:\s*(?:Some\()?)"(?:[^"\\]|\\.)*""#).unwrap();
+    }
+
+    let mut text = text.to_string();
+    for secret in SECRETS.read().unwrap_or_else(PoisonError::into_inner).iter() {
+        text = text.replace(secret.as_str(), REDACTED);
+    }
+
+    let text = BEARER_REGEX.replace_all(&text, format!("${{1}}{REDACTED}"));
+    let text = QUERY_REGEX.replace_all(&text, format!("${{1}}{REDACTED}"));
+    FIELD_REGEX.replace_all(&text, format!("${{1}}\"{REDACTED}\"")).into_owned()
+}
+
+/// Writes to stderr and the log file, after redacting the message.
+struct Logger {
+    level: LevelFilter,
+    /// Most specific module first.
+    module_levels: Vec<(String, LevelFilter)>,
+    file: Mutex<Option<LogFile>>,
+}
+
+impl Logger {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.module_levels.iter()
+            .find(|(module, _)| target == module || target.starts_with(&format!("{module}::")))
+            .map(|(_, level)| *level)
+            .unwrap_or(self.level)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} {}: {}\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            redact(&record.args().to_string()),
+        );
+        eprint!("{line}");
+
+        if let Some(ref mut file) = *self.file.lock().unwrap_or_else(PoisonError::into_inner) {
+            file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(ref mut file) = *self.file.lock().unwrap_or_else(PoisonError::into_inner) {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// `beamer.log`, rotated to `beamer.1.log`, `beamer.2.log` and so on, newest first.
+struct LogFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl LogFile {
+    fn open(dir: &Path, config: &LogConfig) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(dir.join(LOG_FILE_NAME))?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            size: file.metadata()?.len(),
+            file,
+            max_size: config.max_file_size_kb * 1024,
+            max_files: config.max_files,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            if let Err(error) = self.rotate() {
+                eprintln!("could not rotate the log file: {error}");
+            }
+        }
+
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Renaming onto an existing file fails on Windows
+        let _ = fs::remove_file(rotated_path(&self.dir, self.max_files));
+        for number in (1..self.max_files).rev() {
+            let _ = fs::rename(rotated_path(&self.dir, number), rotated_path(&self.dir, number + 1));
+        }
+
+        let path = self.dir.join(LOG_FILE_NAME);
+        if self.max_files > 0 {
+            fs::rename(&path, rotated_path(&self.dir, 1))?;
+        }
+        self.file = File::create(path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+fn rotated_path(dir: &Path, number: usize) -> PathBuf {
+    dir.join(format!("beamer.{number}.log"))
+}
+
+/// The current log file and the rotated ones that exist, newest first.
+pub fn log_files(dir: &Path, config: &LogConfig) -> Vec<PathBuf> {
+    std::iter::once(dir.join(LOG_FILE_NAME))
+        .chain((1..=config.max_files).map(|number| rotated_path(dir, number)))
+        .filter(|x| x.is_file())
+        .collect()
+}
+
+/// `info,app::remote=debug`: a level for everything and levels for modules.
+fn parse_levels(levels: &str, logger: &mut Logger) {
+    for part in levels.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        match part.split_once('=') {
+            Some((module, level)) => {
+                if let Ok(level) = LevelFilter::from_str(level.trim()) {
+                    logger.module_levels.push((module.trim().to_string(), level));
+                }
+            },
+            None => {
+                if let Ok(level) = LevelFilter::from_str(part) {
+                    logger.level = level;
+                }
+            },
+        }
+    }
+}
+
+/// Starts logging. Without a log dir, messages only go to stderr.
+pub fn init(log_dir: Option<&Path>, config: &LogConfig) {
+    let mut logger = Logger {
+        level: LevelFilter::from_str(&config.level).unwrap_or(LevelFilter::Info),
+        module_levels: config.modules.iter()
+            .filter_map(|(module, level)| LevelFilter::from_str(level).ok().map(|level| (module.clone(), level)))
+            .collect(),
+        file: Mutex::new(None),
+    };
+    if let Ok(levels) = std::env::var(LOG_ENV_VAR) {
+        parse_levels(&levels, &mut logger);
+    }
+    // Longer module paths are more specific
+    logger.module_levels.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+
+    let mut file_error = None;
+    if let Some(dir) = log_dir {
+        match LogFile::open(dir, config) {
+            Ok(file) => logger.file = Mutex::new(Some(file)),
+            Err(error) => file_error = Some(error),
+        }
+    }
+
+    let max_level = logger.module_levels.iter()
+        .map(|(_, level)| *level)
+        .chain(std::iter::once(logger.level))
+        .max()
+        .unwrap_or(LevelFilter::Info);
+
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+
+    if let Some(error) = file_error {
+        log::warn!("could not open the log file, only logging to stderr: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_masks_known_secrets() {
+        add_secret("known-secret-value");
+        add_secret("abc");
+
+        assert_eq!(redact("error for known-secret-value"), "error for [redacted]");
+        // Too short to replace
+        assert_eq!(redact("abc"), "abc");
+
+        remove_secret("known-secret-value");
+        assert_eq!(redact("known-secret-value"), "known-secret-value");
+    }
+
+    #[test]
+    fn redact_masks_tokens_pins_and_passwords() {
+        assert_eq!(redact("Authorization: Bearer abc123"), "Authorization: Bearer [redacted]");
+        assert_eq!(redact("GET /api/events?token=abc123&x=1"), "GET /api/events?token=[redacted]&x=1");
+        assert_eq!(redact("GET /?pair_token=abc123"), "GET /?pair_token=[redacted]");
+        assert_eq!(redact(r#"{"pin":"123456","password": "hunter2"}"#), r#"{"pin":"[redacted]","password": "[redacted]"}"#);
+        assert_eq!(redact("POST /api/pair?pin=123456"), "POST /api/pair?pin=[redacted]");
+        assert_eq!(redact(r#"{"spin":"fast"}"#), r#"{"spin":"fast"}"#);
+        assert_eq!(redact(r#"genius_api_token: Some("abc123")"#), r#"genius_api_token: Some("[redacted]")"#);
+        assert_eq!(redact("nothing to hide"), "nothing to hide");
+    }
+
+    #[test]
+    fn parse_levels_sets_the_default_and_module_levels() {
+        let mut logger = Logger {
+            level: LevelFilter::Info,
+            module_levels: vec![],
+            file: Mutex::new(None),
+        };
+        parse_levels("warn, app::remote=debug,app::osc=nonsense,,nonsense", &mut logger);
+
+        assert_eq!(logger.level, LevelFilter::Warn);
+        assert_eq!(logger.module_levels, vec![("app::remote".to_string(), LevelFilter::Debug)]);
+        assert_eq!(logger.level_for("app::remote::auth"), LevelFilter::Debug);
+        assert_eq!(logger.level_for("app::remote_qr"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn rotate_keeps_max_files() {
+        let dir = std::env::temp_dir().join(format!("beamer-log-test-{}", std::process::id()));
+        let config = LogConfig {
+            max_file_size_kb: 1,
+            max_files: 2,
+            ..LogConfig::default()
+        };
+        let mut file = LogFile::open(&dir, &config).unwrap();
+        for _ in 0..10 {
+            file.write_line(&format!("{}\n", "x".repeat(600)));
+        }
+
+        let files = log_files(&dir, &config);
+        let third_exists = rotated_path(&dir, 3).exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, vec![dir.join(LOG_FILE_NAME), rotated_path(&dir, 1), rotated_path(&dir, 2)]);
+        assert!(!third_exists);
+    }
+}
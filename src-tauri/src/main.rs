@@ -7,41 +7,111 @@ use std::error::Error;
 use std::fs;
 use lazy_static::lazy_static;
 use scraper::{Html, Node};
-use song::{Song, SongList, SongSlot, SongSlotType, Verse};
+use beamer_core::song::{Song, SongList, SongSlotType, Verse};
 use regex::Regex;
 use anyhow::Result;
-use tauri::async_runtime::RwLock;
+use std::sync::{Mutex, PoisonError};
+use tokio::sync::broadcast;
 use tauri::Manager;
 use tower_service::Service;
 use querying::{add_searched_song, get_lyrics};
 
-mod song;
 mod config;
 mod display_selection;
 mod querying;
+mod images;
+mod auto_advance;
+mod bible;
+mod countdown;
+mod stage;
+mod overlay;
+mod remote;
+mod blank;
+mod remote_auth;
+mod remote_qr;
+mod osc;
+mod obs;
+mod obs_websocket;
+mod captions;
+mod history;
+mod setlist;
+mod events;
+mod headless;
+mod logging;
+mod diagnostics;
+mod library;
 
-use config::ProgramConfig;
-use config::{get_genius_token, get_font_size, set_genius_token, set_font_size, save_config};
-use display_selection::DisplaySelection;
-use display_selection::{next_verse, previous_verse, get_display_selection};
-use song::SongAddition;
+use config::{sanitized_config, ProgramConfig};
+use config::{get_genius_token, get_font_size, get_remote_config, set_genius_token, set_font_size, set_remote_config, save_config};
+use display_selection::{next_verse, previous_verse, go_to_slot, get_display_selection};
+use beamer_core::session::Session;
+use beamer_core::song::{Countdown, CountdownEndAction, CountdownTarget, CustomSlides, CustomSlidesAddition, CustomSlideAddition, SongAddition};
+use images::add_image;
+use auto_advance::{follow_live_slot, get_loop_mode, start_loop, stop_loop, AutoAdvance};
+use overlay::{clear_overlay, get_overlays, queue_overlay, remove_queued_overlay, OverlayState};
+use stage::{dismiss_stage_message, get_stage_display, get_stage_messages, reset_service_timer, send_stage_message, start_service_timer, StageState};
+use bible::{add_bible_passage, get_bibles, import_bible};
+use blank::{get_blank, set_blank};
+use remote_auth::{get_remote_devices, get_remote_pairing, regenerate_remote_pin, revoke_remote_device, set_new_device_role, set_remote_device_role, RemotePairing};
+use remote_qr::get_remote_qr_code;
+use obs::{get_obs_style, set_obs_style};
+use captions::{export_captions, get_caption_status, start_caption_recording, stop_caption_recording};
+use beamer_core::captions::CaptionLog;
+use obs_websocket::{get_obs_websocket_config, set_obs_websocket_config, start_obs_websocket};
+use osc::{get_osc_config, set_osc_config, start_osc_listener};
+use history::{get_history, redo, undo};
+use setlist::{duplicate_slot, insert_slot, move_slot, remove_slot, replace_slot_song};
+use remote::{remote_event_channel, start_remote_server, RemoteConfig, RemoteEvent};
+use events::WindowEvents;
+use headless::{attach_console, open_windows, print_pairing, CommandLine};
+use diagnostics::export_diagnostics;
+use library::{add_library_song, save_to_library, search_library};
 
 
-/// IMPORTANT: ALWAYS ACQUIRE LOCKS IN ORDER LISTED
-pub struct ProgramState {
-    pub config: RwLock<ProgramConfig>,
-    pub song_list: RwLock<SongList>,
-    pub new_song_id: RwLock<u64>,
-    pub currently_selected: RwLock<DisplaySelection>,
+/// Everything the app keeps track of. Only reachable through `ProgramState::with_state`.
+pub struct AppState {
+    pub config: ProgramConfig,
+    pub session: Session,
+    pub auto_advance: AutoAdvance,
+    pub stage: StageState,
+    pub overlay: OverlayState,
+    pub blank: bool,
+    pub remote_pairing: RemotePairing,
+    pub obs_connected: bool,
+    pub captions: CaptionLog,
+    /// Running without the main window.
+    pub headless: bool,
+    /// The remote server as it was started, with the command line flags applied.
+    /// Without devices: those are kept up to date in `config.remote`.
+    pub remote: RemoteConfig,
     //     ... e.g. currently showing slide
 }
 
+/// All state sits behind a single lock, so there is no lock order to get wrong,
+/// and a change that touches e.g. the song list and the selection is never seen half-done.
+pub struct ProgramState {
+    state: Mutex<AppState>,
+    /// Not part of the state: sending never blocks
+    pub remote_events: broadcast::Sender<RemoteEvent>,
+}
+
+impl ProgramState {
+    /// Runs `f` with exclusive access to the state.
+    ///
+    /// `f` can't await, so the lock is never held across an await point and always released quickly.
+    /// Anything slow, such as network requests, happens before or after. Don't call `with_state` from within `f`.
+    pub fn with_state<T>(&self, f: impl FnOnce(&mut AppState) -> T) -> T {
+        // A panic while holding the lock leaves a state that is still valid
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&mut state)
+    }
+}
+
 #[tauri::command]
 async fn get_songs(
     program_state: tauri::State<'_, ProgramState>,
 ) -> Result<SongList, ()> {
-    let song_list  = program_state.song_list.read().await;
-    Ok((*song_list).clone())
+    Ok(program_state.with_state(|state| state.session.song_list.clone()))
 }
 
 
@@ -50,19 +120,12 @@ async fn update_song_list(
     new_song_list: SongList,
     program_state: tauri::State<'_, ProgramState>,
     app_handle: tauri::AppHandle,
-) -> Result<(), ()> {
-    use tauri::Manager;
-
-    let mut song_list = program_state.song_list.write().await;
-    *song_list = new_song_list;
-
-    let display_selection = program_state.currently_selected.read().await;
-    let display_selection = display_selection.clone();
-    let mut next_display_selection = display_selection.clone();
-    next_display_selection.next(&song_list);
-    app_handle.emit_to("main", "update-display-selection", (display_selection, next_display_selection)).expect("could not emit update-display-selection");
-
-    Ok(())
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        let live_slot_changed = state.session.replace_song_list(&WindowEvents::new(&app_handle, &mut state.captions), new_song_list)?;
+        follow_live_slot(state, &app_handle, live_slot_changed);
+        Ok(())
+    })
 }
 
 
@@ -74,50 +137,81 @@ async fn add_song(
     program_state: tauri::State<'_, ProgramState>,
     app_handle: tauri::AppHandle,
 ) -> Result<SongList, String> {
-    use tauri::Manager;
-
     let song = Song::from_song_addition(SongAddition {
         author: author.to_string(),
         title: title.to_string(),
         song_text: song_text.to_string(),
     });
+    save_to_library(&app_handle, &song);
 
-    add_song_to_state(
-        song,
-        &program_state,
-    ).await;
+    Ok(program_state.with_state(|state| add_slot_to_state(state, &app_handle, SongSlotType::Song(song))))
+}
 
-    let mut song_list = program_state.song_list.write().await;
+#[tauri::command]
+async fn add_custom_slides(
+    title: &str,
+    slides: Vec<CustomSlideAddition>,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SongList, String> {
+    let custom_slides = CustomSlides::from_custom_slides_addition(CustomSlidesAddition {
+        title: title.to_string(),
+        slides,
+    });
 
-    let display_selection = program_state.currently_selected.read().await;
-    let display_selection = display_selection.clone();
-    let mut next_display_selection = display_selection.clone();
-    next_display_selection.next(&song_list);
-    app_handle.emit_to("main", "update-display-selection", (display_selection, next_display_selection)).expect("could not emit update-display-selection");
+    Ok(program_state.with_state(|state| add_slot_to_state(state, &app_handle, SongSlotType::CustomSlides(custom_slides))))
+}
 
-    Ok((*song_list).clone())
+#[tauri::command]
+async fn add_countdown(
+    label: &str,
+    target: CountdownTarget,
+    end_action: CountdownEndAction,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SongList, String> {
+    if let Some(problem) = target.problem() {
+        return Err(format!("The countdown {problem}"));
+    }
+
+    let countdown = Countdown {
+        label: label.to_string(),
+        target,
+        end_action,
+    };
+
+    Ok(program_state.with_state(|state| add_slot_to_state(state, &app_handle, SongSlotType::Countdown(countdown))))
 }
 
-async fn add_song_to_state(
-    song: Song,
-    program_state: &tauri::State<'_, ProgramState>,
-) {
-    let mut song_list = program_state.song_list.write().await;
-    let mut new_song_id = program_state.new_song_id.write().await;
-
-    song_list.songs.push(
-        SongSlot {
-            id: *new_song_id,
-            slot: SongSlotType::Song(song),
-        }
-    );
-    *new_song_id += 1;
+/// Appends `slot` to the song list, and returns the new song list.
+fn add_slot_to_state(
+    state: &mut AppState,
+    app_handle: &tauri::AppHandle,
+    slot: SongSlotType,
+) -> SongList {
+    state.session.add_slot(&WindowEvents::new(app_handle, &mut state.captions), slot);
+    state.session.song_list.clone()
 }
 
 
 fn main() {
-    tauri::Builder::default()
-        .setup(|app| {
+    if std::env::args().any(|x| x == "--help" || x == "-h" || x == "--headless") {
+        attach_console();
+    }
+    if std::env::args().any(|x| x == "--help" || x == "-h") {
+        println!("{}", headless::USAGE);
+        return;
+    }
+    let command_line = match CommandLine::parse(std::env::args().skip(1)) {
+        Ok(command_line) => command_line,
+        Err(error) => {
+            eprintln!("{error}\n\n{}", headless::USAGE);
+            std::process::exit(2);
+        },
+    };
+
+    let app = tauri::Builder::default()
+        .setup(move |app| {
             let mut config_path = app.path_resolver()
                 .app_config_dir()
                 .expect("No config directory found");
@@ -128,23 +222,50 @@ fn main() {
             if config.font_size.len() == 0 {
                 config.font_size = "2.5rem".to_string();
             }
-            println!("config: {config:?}");
-
-            let song_list = SongList {
-                songs: vec![
-                    SongSlot {
-                        id: 0,
-                        slot: SongSlotType::Empty,
-                    },
-                ],
+
+            logging::init(app.path_resolver().app_log_dir().as_deref(), &config.log);
+            logging::add_config_secrets(&config);
+            log::info!("starting {} {}", app.package_info().name, app.package_info().version);
+            log::debug!("config: {:?}", sanitized_config(&config));
+            for arg in &command_line.unknown_args {
+                log::warn!("ignoring unknown argument {arg}");
+            }
+
+            let headless = command_line.headless_config(&config);
+            let remote_config = RemoteConfig {
+                devices: vec![],
+                ..command_line.remote_config(&config, &headless)
             };
-            let display_selection = DisplaySelection::new(&song_list, 0, None);
+            let remote_pairing = RemotePairing::default();
+            let pin = remote_pairing.pin.clone();
+            let config_path = config.config_path.clone();
+            let osc_config = config.osc.clone();
+            let obs_websocket_config = config.obs_websocket.clone();
             (*app).manage(ProgramState {
-                config: RwLock::new(config),
-                song_list: RwLock::new(song_list),
-                new_song_id: RwLock::new(1),
-                currently_selected: RwLock::new(display_selection),
+                state: Mutex::new(AppState {
+                    config,
+                    session: Session::default(),
+                    auto_advance: AutoAdvance::default(),
+                    stage: StageState::default(),
+                    overlay: OverlayState::default(),
+                    blank: false,
+                    remote_pairing,
+                    obs_connected: false,
+                    captions: CaptionLog::default(),
+                    headless: headless.enabled,
+                    remote: remote_config.clone(),
+                }),
+                remote_events: remote_event_channel(),
             });
+            open_windows(app, &headless)?;
+            start_remote_server(app.handle(), &remote_config);
+            if headless.enabled {
+                // Headless from the config file, without the flag
+                attach_console();
+                print_pairing(&remote_config, &config_path, &pin);
+            }
+            start_osc_listener(app.handle(), &osc_config);
+            start_obs_websocket(app.handle(), &obs_websocket_config);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -152,16 +273,77 @@ fn main() {
             get_lyrics,
             add_searched_song,
             add_song,
+            add_image,
+            add_custom_slides,
+            add_countdown,
+            import_bible,
+            get_bibles,
+            add_bible_passage,
             update_song_list,
+            undo,
+            redo,
+            get_history,
+            insert_slot,
+            move_slot,
+            remove_slot,
+            duplicate_slot,
+            replace_slot_song,
             get_genius_token,
             get_font_size,
             set_genius_token,
             set_font_size,
+            get_remote_config,
+            set_remote_config,
+            get_osc_config,
+            set_osc_config,
+            get_obs_style,
+            set_obs_style,
+            get_obs_websocket_config,
+            set_obs_websocket_config,
+            start_caption_recording,
+            stop_caption_recording,
+            get_caption_status,
+            export_captions,
             save_config,
             next_verse,
             previous_verse,
+            go_to_slot,
             get_display_selection,
+            start_loop,
+            stop_loop,
+            get_loop_mode,
+            get_stage_display,
+            start_service_timer,
+            reset_service_timer,
+            send_stage_message,
+            dismiss_stage_message,
+            get_stage_messages,
+            queue_overlay,
+            clear_overlay,
+            remove_queued_overlay,
+            get_overlays,
+            set_blank,
+            get_blank,
+            get_remote_pairing,
+            regenerate_remote_pin,
+            set_new_device_role,
+            get_remote_devices,
+            set_remote_device_role,
+            revoke_remote_device,
+            get_remote_qr_code,
+            export_diagnostics,
+            search_library,
+            add_library_song,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application");
+
+    app.run(|app_handle, event| {
+        // Headless, closing the output windows must not stop the remote API
+        if let tauri::RunEvent::ExitRequested { api, .. } = event {
+            if app_handle.state::<ProgramState>().with_state(|state| state.headless) {
+                api.prevent_exit();
+            }
+        }
+    });
 }
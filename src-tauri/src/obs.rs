@@ -0,0 +1,117 @@
+use std::convert::Infallible;
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Html;
+use axum::Json;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tokio::sync::broadcast;
+use crate::ProgramState;
+use crate::remote::{emit_remote, RemoteEvent};
+
+/// The transparent lyrics page for OBS browser sources, served at `/obs`.
+const OBS_PAGE: &str = include_str!("../assets/obs.html");
+
+/// How the lyrics look in OBS, independent of the presentation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObsStyle {
+    pub font_family: String,
+    pub font_size: String,
+    pub color: String,
+    pub outline_color: String,
+    pub outline_width: String,
+    pub position: ObsPosition,
+    /// Lines beyond this are not shown, `0` shows all lines.
+    pub max_lines: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ObsPosition {
+    Top,
+    Center,
+    Bottom,
+}
+
+impl Default for ObsStyle {
+    fn default() -> Self {
+        Self {
+            font_family: "sans-serif".to_string(),
+            font_size: "3rem".to_string(),
+            color: "#ffffff".to_string(),
+            outline_color: "#000000".to_string(),
+            outline_width: "2px".to_string(),
+            position: ObsPosition::Bottom,
+            max_lines: 2,
+        }
+    }
+}
+
+pub async fn obs_page() -> Html<&'static str> {
+    Html(OBS_PAGE)
+}
+
+pub async fn obs_style(State(app_handle): State<tauri::AppHandle>) -> Json<ObsStyle> {
+    Json(app_handle.state::<ProgramState>().with_state(|state| state.config.obs.clone()))
+}
+
+/// Server-sent events with the same names and payloads as the Tauri events,
+/// starting with the current verse and blank state.
+/// Read-only, so it needs no pairing: OBS can not enter a PIN.
+pub async fn obs_events(State(app_handle): State<tauri::AppHandle>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let program_state = app_handle.state::<ProgramState>();
+    let receiver = program_state.remote_events.subscribe();
+
+    let initial_events = program_state.with_state(|state| vec![
+        RemoteEvent::Verse(state.session.currently_selected.current_verse(&state.session.song_list)),
+        RemoteEvent::Blank(state.blank),
+    ]);
+
+    let events = stream::iter(initial_events)
+        .chain(stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    // Missed some events: the next one is still up to date
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }))
+        .filter_map(|event| async move { sse_event(&event) })
+        .map(Ok);
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+fn sse_event(event: &RemoteEvent) -> Option<Event> {
+    let (name, data) = match event {
+        RemoteEvent::Verse(verse) => ("update-verse", serde_json::to_string(verse)),
+        RemoteEvent::Blank(blank) => ("update-blank", serde_json::to_string(blank)),
+        RemoteEvent::ObsStyle(style) => ("update-obs-style", serde_json::to_string(style)),
+        RemoteEvent::Selection(_) | RemoteEvent::SongList(_) | RemoteEvent::CaptionCue(_) => return None,
+    };
+
+    data.ok().map(|data| Event::default().event(name).data(data))
+}
+
+
+#[tauri::command]
+pub async fn get_obs_style(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<ObsStyle, ()> {
+    Ok(program_state.with_state(|state| state.config.obs.clone()))
+}
+
+#[tauri::command]
+pub async fn set_obs_style(
+    new_obs_style: ObsStyle,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), ()> {
+    program_state.with_state(|state| {
+        state.config.obs = new_obs_style.clone();
+        emit_remote(&app_handle, RemoteEvent::ObsStyle(new_obs_style));
+    });
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use std::time::Duration;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use beamer_core::display_selection::SlotKind;
+use crate::ProgramState;
+use crate::remote::RemoteEvent;
+use crate::events::emit_to;
+use crate::logging::add_secret;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const RPC_VERSION: u64 = 1;
+
+type ObsSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Connection to OBS Studio's WebSocket server (protocol v5), and what to change in OBS.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObsWebSocketConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub password: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<ObsRule>,
+}
+
+impl Default for ObsWebSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 4455,
+            password: None,
+            rules: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObsRule {
+    pub trigger: ObsTrigger,
+    pub action: ObsAction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObsTrigger {
+    /// A song slot goes live.
+    SongStart,
+    /// An empty slot goes live.
+    EmptySlot,
+    CountdownSlot,
+    ImageSlot,
+    /// The presentation is blanked.
+    Blank,
+    Unblank,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ObsAction {
+    SwitchScene {
+        scene_name: String,
+    },
+    SetSourceVisibility {
+        scene_name: String,
+        source_name: String,
+        visible: bool,
+    },
+}
+
+/// Keeps a connection to OBS in the background, if enabled in the config, and applies the rules.
+pub fn start_obs_websocket(app_handle: tauri::AppHandle, config: &ObsWebSocketConfig) {
+    if !config.enabled {
+        return;
+    }
+
+    let config = config.clone();
+    let mut events = app_handle.state::<ProgramState>().remote_events.subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match connect(&config).await {
+                Ok(mut socket) => {
+                    set_connected(&app_handle, true);
+                    // Only react to what happens while connected
+                    events = events.resubscribe();
+
+                    if let Err(error) = apply_rules(&config, &mut socket, &mut events).await {
+                        log::warn!("connection lost: {error}");
+                    }
+                    set_connected(&app_handle, false);
+                },
+                Err(error) => log::warn!("could not connect: {error}"),
+            }
+
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+fn set_connected(app_handle: &tauri::AppHandle, connected: bool) {
+    app_handle.state::<ProgramState>().with_state(|state| state.obs_connected = connected);
+
+    emit_to(app_handle, "main", "update-obs-connection", connected);
+}
+
+/// Connects and identifies, authenticating with the password when OBS asks for it.
+async fn connect(config: &ObsWebSocketConfig) -> Result<ObsSocket, String> {
+    let url = format!("ws://{}:{}", config.host, config.port);
+    let (mut socket, _) = tokio_tungstenite::connect_async(&url).await.map_err(|x| x.to_string())?;
+
+    let hello = receive_op(&mut socket, 0).await?;
+    let mut identify = json!({
+        "rpcVersion": RPC_VERSION,
+        // Rules only need our own requests
+        "eventSubscriptions": 0,
+    });
+
+    if let Some(authentication) = hello.get("authentication") {
+        let challenge = authentication["challenge"].as_str().ok_or("Invalid hello from OBS".to_string())?;
+        let salt = authentication["salt"].as_str().ok_or("Invalid hello from OBS".to_string())?;
+        let password = config.password.as_deref().ok_or("OBS requires a password".to_string())?;
+
+        identify["authentication"] = Value::String(authentication_string(password, salt, challenge));
+    }
+
+    send_op(&mut socket, 1, identify).await?;
+    receive_op(&mut socket, 2).await
+        .map_err(|_| "OBS did not accept the password".to_string())?;
+
+    Ok(socket)
+}
+
+/// `base64(sha256(base64(sha256(password + salt)) + challenge))`
+fn authentication_string(password: &str, salt: &str, challenge: &str) -> String {
+    let base64 = base64::engine::general_purpose::STANDARD;
+
+    let secret = base64.encode(Sha256::digest(format!("{password}{salt}")));
+    base64.encode(Sha256::digest(format!("{secret}{challenge}")))
+}
+
+async fn apply_rules(
+    config: &ObsWebSocketConfig,
+    socket: &mut ObsSocket,
+    events: &mut broadcast::Receiver<RemoteEvent>,
+) -> Result<(), String> {
+    let mut live_slot_id = None;
+    let mut request_id = 0u64;
+
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            // Notices a closed connection while nothing happens on the presentation
+            message = socket.next() => match message {
+                Some(Ok(_)) => continue,
+                Some(Err(error)) => return Err(error.to_string()),
+                None => return Err("closed by OBS".to_string()),
+            },
+        };
+
+        let trigger = match event {
+            RemoteEvent::Selection(update) => {
+                let selection = &update.current;
+                // Also sent when only the verse or song list changed
+                if live_slot_id == Some(selection.slot_id) {
+                    continue;
+                }
+                live_slot_id = Some(selection.slot_id);
+
+                match selection.kind {
+                    SlotKind::Song => ObsTrigger::SongStart,
+                    SlotKind::Empty => ObsTrigger::EmptySlot,
+                    SlotKind::Countdown => ObsTrigger::CountdownSlot,
+                    SlotKind::Image => ObsTrigger::ImageSlot,
+                    SlotKind::CustomSlides | SlotKind::Bible => continue,
+                }
+            },
+            RemoteEvent::Blank(true) => ObsTrigger::Blank,
+            RemoteEvent::Blank(false) => ObsTrigger::Unblank,
+            _ => continue,
+        };
+
+        for rule in config.rules.iter().filter(|x| x.trigger == trigger) {
+            if let Err(error) = run_action(socket, &rule.action, &mut request_id).await {
+                log::warn!("{trigger:?} rule failed: {error}");
+            }
+        }
+    }
+}
+
+async fn run_action(socket: &mut ObsSocket, action: &ObsAction, request_id: &mut u64) -> Result<(), String> {
+    match action {
+        ObsAction::SwitchScene { scene_name } => {
+            request(socket, request_id, "SetCurrentProgramScene", json!({ "sceneName": scene_name })).await?;
+        },
+        ObsAction::SetSourceVisibility { scene_name, source_name, visible } => {
+            let response = request(socket, request_id, "GetSceneItemId", json!({
+                "sceneName": scene_name,
+                "sourceName": source_name,
+            })).await?;
+            let scene_item_id = response["sceneItemId"].as_u64().ok_or(format!("Source '{source_name}' not found"))?;
+
+            request(socket, request_id, "SetSceneItemEnabled", json!({
+                "sceneName": scene_name,
+                "sceneItemId": scene_item_id,
+                "sceneItemEnabled": visible,
+            })).await?;
+        },
+    }
+
+    Ok(())
+}
+
+/// Sends a request and waits for its response data.
+async fn request(socket: &mut ObsSocket, request_id: &mut u64, request_type: &str, request_data: Value) -> Result<Value, String> {
+    *request_id += 1;
+    let id = request_id.to_string();
+
+    send_op(socket, 6, json!({
+        "requestType": request_type,
+        "requestId": id,
+        "requestData": request_data,
+    })).await?;
+
+    loop {
+        let response = receive_op(socket, 7).await?;
+        if response["requestId"].as_str() != Some(&id) {
+            continue;
+        }
+
+        if response["requestStatus"]["result"].as_bool() != Some(true) {
+            let comment = response["requestStatus"]["comment"].as_str().unwrap_or("unknown error");
+            return Err(format!("{request_type}: {comment}"));
+        }
+        return Ok(response.get("responseData").cloned().unwrap_or(Value::Null));
+    }
+}
+
+async fn send_op(socket: &mut ObsSocket, op: u64, data: Value) -> Result<(), String> {
+    let message = json!({ "op": op, "d": data });
+    socket.send(Message::Text(message.to_string())).await.map_err(|x| x.to_string())
+}
+
+/// Waits for a message with the given op code, skipping others, and returns its data.
+async fn receive_op(socket: &mut ObsSocket, op: u64) -> Result<Value, String> {
+    loop {
+        let message = socket.next().await
+            .ok_or("closed by OBS".to_string())?
+            .map_err(|x| x.to_string())?;
+
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => return Err("closed by OBS".to_string()),
+            _ => continue,
+        };
+
+        let mut message: Value = serde_json::from_str(&text).map_err(|_| "Invalid message from OBS".to_string())?;
+        if message["op"].as_u64() == Some(op) {
+            return Ok(message["d"].take());
+        }
+    }
+}
+
+
+#[tauri::command]
+pub async fn get_obs_websocket_config(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(ObsWebSocketConfig, bool), ()> { // Ok(config, connected)
+    Ok(program_state.with_state(|state| (state.config.obs_websocket.clone(), state.obs_connected)))
+}
+
+/// Takes effect after restarting the app.
+#[tauri::command]
+pub async fn set_obs_websocket_config(
+    new_obs_websocket_config: ObsWebSocketConfig,
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(), ()> {
+    if let Some(ref password) = new_obs_websocket_config.password {
+        add_secret(password);
+    }
+    program_state.with_state(|state| state.config.obs_websocket = new_obs_websocket_config);
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use std::net::SocketAddr;
+use rosc::{OscMessage, OscPacket, OscType};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast;
+use crate::ProgramState;
+use crate::blank::{set_blanked, toggle_blanked};
+use crate::display_selection::{select_next, select_previous, select_slot};
+use crate::remote::RemoteEvent;
+
+const MAX_PACKET_SIZE: usize = 1536;
+
+/// Lets lighting desks and show controllers navigate with OSC over UDP.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OscConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub port: u16,
+    /// `host:port` addresses that receive the current song and verse.
+    #[serde(default)]
+    pub feedback_targets: Vec<String>,
+}
+
+impl Default for OscConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "0.0.0.0".to_string(),
+            port: 8000,
+            feedback_targets: vec![],
+        }
+    }
+}
+
+/// Starts listening for OSC messages in the background, if enabled in the config.
+pub fn start_osc_listener(app_handle: tauri::AppHandle, config: &OscConfig) {
+    if !config.enabled {
+        return;
+    }
+
+    let address = format!("{}:{}", config.bind_address, config.port);
+    let feedback_targets = config.feedback_targets.iter()
+        .filter_map(|x| match x.parse::<SocketAddr>() {
+            Ok(target) => Some(target),
+            Err(_) => {
+                log::warn!("invalid feedback target {x}");
+                None
+            },
+        })
+        .collect::<Vec<_>>();
+
+    tauri::async_runtime::spawn(async move {
+        let socket = match UdpSocket::bind(&address).await {
+            Ok(socket) => socket,
+            Err(error) => {
+                log::error!("could not bind to {address}: {error}");
+                return;
+            },
+        };
+
+        if !feedback_targets.is_empty() {
+            let events = app_handle.state::<ProgramState>().remote_events.subscribe();
+            tauri::async_runtime::spawn(send_feedback(feedback_targets, events));
+        }
+
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        loop {
+            let size = match socket.recv_from(&mut buffer).await {
+                Ok((size, _)) => size,
+                Err(error) => {
+                    log::warn!("could not receive: {error}");
+                    continue;
+                },
+            };
+
+            match rosc::decoder::decode_udp(&buffer[..size]) {
+                Ok((_, packet)) => handle_packet(&app_handle, packet),
+                Err(error) => log::warn!("invalid packet: {error:?}"),
+            }
+        }
+    });
+}
+
+/// Bundles are handled in order, ignoring their time tag.
+fn handle_packet(app_handle: &tauri::AppHandle, packet: OscPacket) {
+    let mut packets = vec![packet];
+
+    while let Some(packet) = packets.pop() {
+        match packet {
+            OscPacket::Message(message) => {
+                if let Err(error) = handle_message(app_handle, &message) {
+                    log::warn!("{} failed: {error}", message.addr);
+                }
+            },
+            OscPacket::Bundle(bundle) => packets.extend(bundle.content.into_iter().rev()),
+        }
+    }
+}
+
+/// `/beamer/goto` takes the slot position and verse as shown in the main window, both counted from 0.
+/// `/beamer/blank` toggles without an argument, or sets the blank screen with `1` or `0`.
+fn handle_message(app_handle: &tauri::AppHandle, message: &OscMessage) -> Result<(), String> {
+    let program_state = app_handle.state::<ProgramState>();
+
+    match message.addr.as_str() {
+        "/beamer/next" => select_next(&program_state, app_handle),
+        "/beamer/previous" => select_previous(&program_state, app_handle),
+        "/beamer/goto" => {
+            let slot_position = message.args.first().and_then(integer_argument).ok_or("Missing slot".to_string())?;
+            let verse_num = message.args.get(1).and_then(integer_argument).unwrap_or(0);
+
+            let slot_id = program_state.with_state(|state| state.session.song_list.songs.get(slot_position as usize).map(|x| x.id))
+                .ok_or(format!("No slot at position {slot_position}"))?;
+            select_slot(&program_state, app_handle, slot_id, Some(verse_num as usize))?;
+        },
+        "/beamer/blank" => {
+            match message.args.first().and_then(integer_argument) {
+                Some(value) => set_blanked(&program_state, app_handle, value != 0),
+                None => toggle_blanked(&program_state, app_handle),
+            }
+        },
+        address => return Err(format!("Unknown address {address}")),
+    }
+
+    Ok(())
+}
+
+/// Desks send numbers as ints, floats or strings.
+fn integer_argument(argument: &OscType) -> Option<u64> {
+    match argument {
+        OscType::Int(value) if *value >= 0 => Some(*value as u64),
+        OscType::Long(value) if *value >= 0 => Some(*value as u64),
+        OscType::Float(value) if *value >= 0.0 => Some(*value as u64),
+        OscType::Double(value) if *value >= 0.0 => Some(*value as u64),
+        OscType::Bool(value) => Some(*value as u64),
+        OscType::String(value) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Sends `/beamer/song <title>`, `/beamer/verse <slot position> <verse>` and `/beamer/blank <0|1>`
+/// to the feedback targets when they change.
+async fn send_feedback(targets: Vec<SocketAddr>, mut events: broadcast::Receiver<RemoteEvent>) {
+    let socket = match UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => socket,
+        Err(error) => {
+            log::error!("could not open feedback socket: {error}");
+            return;
+        },
+    };
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let messages = match event {
+            RemoteEvent::Selection(update) => {
+                let selection = update.current;
+                vec![
+                    OscMessage {
+                        addr: "/beamer/song".to_string(),
+                        args: vec![OscType::String(selection.title)],
+                    },
+                    OscMessage {
+                        addr: "/beamer/verse".to_string(),
+                        args: vec![OscType::Int(selection.slot_position as i32), OscType::Int(selection.verse_num as i32)],
+                    },
+                ]
+            },
+            RemoteEvent::Blank(blank) => vec![
+                OscMessage {
+                    addr: "/beamer/blank".to_string(),
+                    args: vec![OscType::Int(blank as i32)],
+                },
+            ],
+            RemoteEvent::Verse(_) | RemoteEvent::SongList(_) | RemoteEvent::ObsStyle(_) | RemoteEvent::CaptionCue(_) => continue,
+        };
+
+        for message in messages {
+            let packet = match rosc::encoder::encode(&OscPacket::Message(message)) {
+                Ok(packet) => packet,
+                Err(_) => continue,
+            };
+            for target in &targets {
+                let _ = socket.send_to(&packet, target).await;
+            }
+        }
+    }
+}
+
+
+#[tauri::command]
+pub async fn get_osc_config(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<OscConfig, ()> {
+    Ok(program_state.with_state(|state| state.config.osc.clone()))
+}
+
+/// Takes effect after restarting the app.
+#[tauri::command]
+pub async fn set_osc_config(
+    new_osc_config: OscConfig,
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(), ()> {
+    program_state.with_state(|state| state.config.osc = new_osc_config);
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use crate::ProgramState;
+use crate::events::emit_to;
+
+/// A message shown on top of whatever is live, e.g. to call parents to the nursery.
+/// Independent of the `DisplaySelection`.
+#[derive(Clone, Debug, Serialize)]
+pub struct OverlayMessage {
+    pub id: u64,
+    pub text: String,
+    pub style: OverlayStyle,
+    pub position: OverlayPosition,
+    pub duration_seconds: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum OverlayStyle {
+    /// Scrolls across the screen.
+    Ticker,
+    /// Stays in place.
+    Banner,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum OverlayPosition {
+    Top,
+    Bottom,
+}
+
+/// Messages are shown one at a time, in the order they were queued.
+#[derive(Clone, Debug, Default)]
+pub struct OverlayState {
+    pub current: Option<OverlayMessage>,
+    pub queue: VecDeque<OverlayMessage>,
+    pub new_overlay_id: u64,
+}
+
+/// Shows the next queued message if nothing is showing.
+fn show_next_overlay(app_handle: &tauri::AppHandle, overlay: &mut OverlayState) {
+    if overlay.current.is_none() {
+        overlay.current = overlay.queue.pop_front();
+
+        if let Some(ref message) = overlay.current {
+            start_overlay_timer(app_handle.clone(), message.id, message.duration_seconds);
+        }
+    }
+
+    emit_overlay(app_handle, overlay);
+}
+
+fn emit_overlay(app_handle: &tauri::AppHandle, overlay: &OverlayState) {
+    emit_to(app_handle, "presentation", "update-overlay", &overlay.current);
+    emit_to(app_handle, "main", "update-overlay-queue", (&overlay.current, &overlay.queue));
+}
+
+fn start_overlay_timer(app_handle: tauri::AppHandle, overlay_id: u64, seconds: u64) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(seconds)).await;
+
+        app_handle.state::<ProgramState>().with_state(|state| {
+            let overlay = &mut state.overlay;
+
+            // Cleared by the operator in the meantime
+            if overlay.current.as_ref().map(|x| x.id) != Some(overlay_id) {
+                return;
+            }
+
+            overlay.current = None;
+            show_next_overlay(&app_handle, overlay);
+        });
+    });
+}
+
+
+#[tauri::command]
+pub async fn queue_overlay(
+    text: &str,
+    style: OverlayStyle,
+    position: OverlayPosition,
+    duration_seconds: u64,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<OverlayMessage, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("The overlay text is empty".to_string());
+    }
+    if duration_seconds == 0 {
+        return Err("The overlay must be shown for at least one second".to_string());
+    }
+
+    Ok(program_state.with_state(|state| {
+        let overlay = &mut state.overlay;
+
+        let message = OverlayMessage {
+            id: overlay.new_overlay_id,
+            text: text.to_string(),
+            style,
+            position,
+            duration_seconds,
+        };
+        overlay.new_overlay_id += 1;
+        overlay.queue.push_back(message.clone());
+
+        show_next_overlay(&app_handle, overlay);
+        message
+    }))
+}
+
+/// Hides the current message, the next queued message is shown instead.
+#[tauri::command]
+pub async fn clear_overlay(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        state.overlay.current = None;
+        show_next_overlay(&app_handle, &mut state.overlay);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_queued_overlay(
+    overlay_id: u64,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        state.overlay.queue.retain(|x| x.id != overlay_id);
+        emit_overlay(&app_handle, &state.overlay);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_overlays(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(Option<OverlayMessage>, VecDeque<OverlayMessage>), String> { // Ok(current, queue)
+    Ok(program_state.with_state(|state| (state.overlay.current.clone(), state.overlay.queue.clone())))
+}
@@ -0,0 +1,362 @@
+use std::net::SocketAddr;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tokio::sync::broadcast;
+use beamer_core::captions::CaptionCue;
+use beamer_core::display_selection::SelectionUpdate;
+use beamer_core::setlist::SongListUpdate;
+use beamer_core::song::{SongList, Verse};
+use crate::ProgramState;
+use crate::auto_advance::follow_live_slot;
+use crate::blank::set_blanked;
+use crate::captions::{live_text, live_webvtt};
+use crate::obs::{obs_events, obs_page, obs_style, ObsStyle};
+use crate::remote_auth::{authorize, pair_with_pin, pair_with_token, request_token, RemoteDevice, RemoteRole};
+use crate::display_selection::{display_selections, select_next, select_previous, select_slot};
+use crate::events::WindowEvents;
+
+/// The mobile remote page, served at `/`.
+const REMOTE_PAGE: &str = include_str!("../assets/remote.html");
+
+/// How many events a slow WebSocket client may fall behind before it misses some.
+const EVENT_BUFFER: usize = 32;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub port: u16,
+    #[serde(default)]
+    pub devices: Vec<RemoteDevice>,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "0.0.0.0".to_string(),
+            port: 7878,
+            devices: vec![],
+        }
+    }
+}
+
+/// Events pushed to WebSocket clients and OSC feedback targets,
+/// with the same names and payloads as the Tauri events.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", content = "payload")]
+pub enum RemoteEvent {
+    #[serde(rename = "update-verse")]
+    Verse(Verse),
+    #[serde(rename = "update-display-selection")]
+    Selection(Box<SelectionUpdate>),
+    #[serde(rename = "update-song-list")]
+    SongList(SongListUpdate),
+    #[serde(rename = "update-blank")]
+    Blank(bool),
+    #[serde(rename = "update-obs-style")]
+    ObsStyle(ObsStyle),
+    /// A caption cue that ended while recording.
+    #[serde(rename = "caption-cue")]
+    CaptionCue(CaptionCue),
+}
+
+pub fn remote_event_channel() -> broadcast::Sender<RemoteEvent> {
+    broadcast::channel(EVENT_BUFFER).0
+}
+
+/// Forwards an event to all connected WebSocket clients.
+pub fn emit_remote(app_handle: &tauri::AppHandle, event: RemoteEvent) {
+    let program_state = app_handle.state::<ProgramState>();
+    // Fails only when no client is connected
+    let _ = program_state.remote_events.send(event);
+}
+
+/// Starts the HTTP server in the background, if enabled in the config.
+pub fn start_remote_server(app_handle: tauri::AppHandle, config: &RemoteConfig) {
+    if !config.enabled {
+        return;
+    }
+
+    let address = format!("{}:{}", config.bind_address, config.port);
+    let address: SocketAddr = match address.parse() {
+        Ok(address) => address,
+        Err(_) => {
+            log::error!("invalid bind address {address}");
+            return;
+        },
+    };
+
+    let router = Router::new()
+        .route("/", get(remote_page))
+        .route("/api/pair", post(pair))
+        .route("/api/device", get(device))
+        .route("/api/next", post(next))
+        .route("/api/previous", post(previous))
+        .route("/api/goto", post(goto))
+        .route("/api/songs", get(songs).put(set_songs))
+        .route("/api/display-selection", get(display_selection))
+        .route("/api/blank", get(blank).post(set_blank))
+        .route("/api/events", get(events))
+        .route("/obs", get(obs_page))
+        .route("/obs/style", get(obs_style))
+        .route("/obs/events", get(obs_events))
+        .route("/captions/live.vtt", get(live_webvtt))
+        .route("/captions/live.txt", get(live_text))
+        .with_state(app_handle);
+
+    tauri::async_runtime::spawn(async move {
+        let server = match axum::Server::try_bind(&address) {
+            Ok(server) => server,
+            Err(error) => {
+                log::error!("could not bind to {address}: {error}");
+                return;
+            },
+        };
+        log::info!("listening on {address}");
+
+        if let Err(error) = server.serve(router.into_make_service()).await {
+            log::error!("server stopped: {error}");
+        }
+    });
+}
+
+#[derive(Deserialize)]
+struct AuthQuery {
+    token: Option<String>,
+}
+
+/// Pairs with either the PIN or the one-time token from the QR code.
+#[derive(Deserialize)]
+struct PairRequest {
+    pin: Option<String>,
+    pairing_token: Option<String>,
+    device_name: String,
+}
+
+#[derive(Serialize)]
+struct PairResponse {
+    token: String,
+    role: RemoteRole,
+}
+
+#[derive(Deserialize)]
+struct BlankRequest {
+    blank: bool,
+}
+
+#[derive(Deserialize)]
+struct GotoRequest {
+    slot_id: u64,
+    verse_num: Option<usize>,
+}
+
+type ApiResult<T> = Result<T, (StatusCode, String)>;
+
+fn authorize_request(
+    app_handle: &tauri::AppHandle,
+    headers: &HeaderMap,
+    query: AuthQuery,
+    required_role: RemoteRole,
+) -> ApiResult<RemoteRole> {
+    let token = request_token(headers, query.token);
+    authorize(app_handle, token.as_deref(), required_role)
+}
+
+async fn remote_page() -> Html<&'static str> {
+    Html(REMOTE_PAGE)
+}
+
+async fn pair(
+    State(app_handle): State<tauri::AppHandle>,
+    Json(request): Json<PairRequest>,
+) -> ApiResult<Json<PairResponse>> {
+    if request.device_name.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No device name given".to_string()));
+    }
+
+    let program_state = app_handle.state::<ProgramState>();
+    let device = match (request.pairing_token, request.pin) {
+        (Some(pairing_token), _) => program_state.with_state(|state| pair_with_token(state, &app_handle, &pairing_token, &request.device_name)),
+        (None, Some(pin)) => program_state.with_state(|state| pair_with_pin(state, &app_handle, &pin, &request.device_name)),
+        (None, None) => Err("No PIN given".to_string()),
+    }.map_err(|x| (StatusCode::UNAUTHORIZED, x))?;
+
+    Ok(Json(PairResponse {
+        token: device.token,
+        role: device.role,
+    }))
+}
+
+async fn device(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+) -> ApiResult<Json<RemoteRole>> {
+    let role = authorize_request(&app_handle, &headers, query, RemoteRole::ViewOnly)?;
+    Ok(Json(role))
+}
+
+async fn next(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+) -> ApiResult<StatusCode> {
+    authorize_request(&app_handle, &headers, query, RemoteRole::Presenter)?;
+
+    select_next(&app_handle.state::<ProgramState>(), &app_handle);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn previous(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+) -> ApiResult<StatusCode> {
+    authorize_request(&app_handle, &headers, query, RemoteRole::Presenter)?;
+
+    select_previous(&app_handle.state::<ProgramState>(), &app_handle);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn goto(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+    Json(request): Json<GotoRequest>,
+) -> ApiResult<StatusCode> {
+    authorize_request(&app_handle, &headers, query, RemoteRole::Presenter)?;
+
+    select_slot(&app_handle.state::<ProgramState>(), &app_handle, request.slot_id, request.verse_num)
+        .map_err(|x| (StatusCode::NOT_FOUND, x.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The song list with the version of the last `update-song-list`, so a client can apply the updates after it.
+#[derive(Serialize)]
+struct VersionedSongList {
+    version: u64,
+    #[serde(flatten)]
+    song_list: SongList,
+}
+
+async fn songs(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+) -> ApiResult<Json<VersionedSongList>> {
+    authorize_request(&app_handle, &headers, query, RemoteRole::ViewOnly)?;
+
+    Ok(Json(app_handle.state::<ProgramState>().with_state(|state| VersionedSongList {
+        version: state.session.song_list_version,
+        song_list: state.session.song_list.clone(),
+    })))
+}
+
+async fn set_songs(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+    Json(new_song_list): Json<SongList>,
+) -> ApiResult<StatusCode> {
+    authorize_request(&app_handle, &headers, query, RemoteRole::FullControl)?;
+
+    app_handle.state::<ProgramState>().with_state(|state| {
+        let live_slot_changed = state.session.replace_song_list(&WindowEvents::new(&app_handle, &mut state.captions), new_song_list)
+            .map_err(|x| (StatusCode::BAD_REQUEST, x))?;
+        follow_live_slot(state, &app_handle, live_slot_changed);
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn display_selection(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+) -> ApiResult<Json<SelectionUpdate>> {
+    authorize_request(&app_handle, &headers, query, RemoteRole::ViewOnly)?;
+
+    Ok(Json(display_selections(&app_handle.state::<ProgramState>())))
+}
+
+async fn blank(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+) -> ApiResult<Json<bool>> {
+    authorize_request(&app_handle, &headers, query, RemoteRole::ViewOnly)?;
+
+    Ok(Json(app_handle.state::<ProgramState>().with_state(|state| state.blank)))
+}
+
+async fn set_blank(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+    Json(request): Json<BlankRequest>,
+) -> ApiResult<StatusCode> {
+    authorize_request(&app_handle, &headers, query, RemoteRole::Presenter)?;
+
+    set_blanked(&app_handle.state::<ProgramState>(), &app_handle, request.blank);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn events(
+    State(app_handle): State<tauri::AppHandle>,
+    headers: HeaderMap,
+    Query(query): Query<AuthQuery>,
+    upgrade: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let token = request_token(&headers, query.token);
+    authorize(&app_handle, token.as_deref(), RemoteRole::ViewOnly)?;
+
+    let receiver = app_handle.state::<ProgramState>().remote_events.subscribe();
+    // Checked above
+    let token = token.unwrap_or_default();
+    Ok(upgrade.on_upgrade(move |socket| forward_events(app_handle, token, socket, receiver)))
+}
+
+async fn forward_events(
+    app_handle: tauri::AppHandle,
+    token: String,
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<RemoteEvent>,
+) {
+    loop {
+        tokio::select! {
+            event = receiver.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    // Missed some events: the next one is still up to date
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                // The device may have been revoked since connecting
+                if authorize(&app_handle, Some(&token), RemoteRole::ViewOnly).is_err() {
+                    break;
+                }
+
+                let json = match serde_json::to_string(&event) {
+                    Ok(json) => json,
+                    Err(_) => continue,
+                };
+                if socket.send(Message::Text(json)).await.is_err() {
+                    break;
+                }
+            },
+            message = socket.recv() => {
+                // Clients only listen; anything but a message means the connection closed
+                if !matches!(message, Some(Ok(_))) {
+                    break;
+                }
+            },
+        }
+    }
+}
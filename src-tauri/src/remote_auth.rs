@@ -0,0 +1,291 @@
+use axum::http::{HeaderMap, StatusCode};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use subtle::ConstantTimeEq;
+use tauri::Manager;
+use crate::{AppState, ProgramState};
+use crate::config::{write_config, ProgramConfig};
+use crate::stage::unix_millis;
+use crate::events::emit_to;
+use crate::headless::show_pin;
+use crate::logging::{add_secret, remove_secret};
+
+/// After this many wrong PINs, a new PIN is generated.
+const MAX_PIN_ATTEMPTS: u32 = 5;
+/// Wait after a wrong PIN before the next attempt, so PINs can not be tried in bulk.
+const PIN_RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Wait after too many wrong PINs.
+const PIN_LOCKOUT: Duration = Duration::from_secs(60);
+const TOKEN_LENGTH: usize = 32;
+
+/// What a paired device may do. Every role can do everything the roles before it can.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RemoteRole {
+    /// Follow along: song list, selection and events.
+    ViewOnly,
+    /// Navigate and blank the screen.
+    Presenter,
+    /// Also change the song list.
+    FullControl,
+}
+
+/// A device that paired with the PIN, stored in the config.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoteDevice {
+    pub id: u64,
+    pub name: String,
+    pub token: String,
+    pub role: RemoteRole,
+    pub paired_at: u64,
+}
+
+/// The PIN shown in the operator window. Regenerated on every start, so it is not stored.
+#[derive(Clone, Debug)]
+pub struct RemotePairing {
+    pub pin: String,
+    pub new_device_role: RemoteRole,
+    pub failed_attempts: u32,
+    /// No PIN is checked before this moment, after a wrong PIN.
+    pub next_attempt_at: Option<Instant>,
+    /// Pairs a single device without the PIN, from the QR code.
+    pub pairing_token: Option<String>,
+}
+
+impl Default for RemotePairing {
+    fn default() -> Self {
+        Self {
+            pin: generate_pin(),
+            new_device_role: RemoteRole::Presenter,
+            failed_attempts: 0,
+            next_attempt_at: None,
+            pairing_token: None,
+        }
+    }
+}
+
+fn generate_pin() -> String {
+    format!("{:06}", rand::thread_rng().gen_range(0..1_000_000))
+}
+
+/// The token is kept out of the log from the start.
+pub fn generate_token() -> String {
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+    add_secret(&token);
+
+    token
+}
+
+/// Compares in constant time, so the time to reject a token or PIN does not tell how much of it was right.
+fn secrets_match(a: &str, b: &str) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+/// Adds a device to the config and saves it. Returns the token for the device.
+pub fn add_device(config: &mut ProgramConfig, name: &str, role: RemoteRole) -> Result<RemoteDevice, String> {
+    let devices = &mut config.remote.devices;
+    let device = RemoteDevice {
+        id: devices.iter().map(|x| x.id + 1).max().unwrap_or(0),
+        name: name.trim().to_string(),
+        token: generate_token(),
+        role,
+        paired_at: unix_millis()?,
+    };
+    devices.push(device.clone());
+
+    write_config(config)?;
+    Ok(device)
+}
+
+/// Checks the PIN and pairs the device. Too many wrong PINs invalidate the PIN.
+pub fn pair_with_pin(
+    state: &mut AppState,
+    app_handle: &tauri::AppHandle,
+    pin: &str,
+    device_name: &str,
+) -> Result<RemoteDevice, String> {
+    let pairing = &mut state.remote_pairing;
+
+    let now = Instant::now();
+    if pairing.next_attempt_at.map_or(false, |x| now < x) {
+        return Err("Too many attempts, try again later".to_string());
+    }
+
+    if !secrets_match(pin.trim(), &pairing.pin) {
+        pairing.failed_attempts += 1;
+        pairing.next_attempt_at = Some(now + PIN_RETRY_DELAY);
+        if pairing.failed_attempts >= MAX_PIN_ATTEMPTS {
+            pairing.pin = generate_pin();
+            pairing.failed_attempts = 0;
+            pairing.next_attempt_at = Some(now + PIN_LOCKOUT);
+            emit_remote_pin(app_handle, pairing);
+            if state.headless {
+                log::warn!("too many wrong PINs, generated a new PIN");
+                show_pin(&state.config.config_path, &pairing.pin);
+            }
+        }
+        return Err("Wrong PIN".to_string());
+    }
+    pairing.failed_attempts = 0;
+    pairing.next_attempt_at = None;
+
+    let device = add_device(&mut state.config, device_name, pairing.new_device_role)?;
+    emit_remote_devices(app_handle, &state.config);
+    Ok(device)
+}
+
+/// Pairs the device with the one-time token from the QR code, which can not be used again.
+pub fn pair_with_token(
+    state: &mut AppState,
+    app_handle: &tauri::AppHandle,
+    pairing_token: &str,
+    device_name: &str,
+) -> Result<RemoteDevice, String> {
+    let pairing = &mut state.remote_pairing;
+
+    if !pairing.pairing_token.as_deref().map_or(false, |x| secrets_match(x, pairing_token)) {
+        return Err("This QR code was already used or replaced".to_string());
+    }
+    if let Some(used_token) = pairing.pairing_token.take() {
+        remove_secret(&used_token);
+    }
+
+    let device = add_device(&mut state.config, device_name, pairing.new_device_role)?;
+    emit_remote_devices(app_handle, &state.config);
+    Ok(device)
+}
+
+/// The token from an `Authorization: Bearer` header, or from the `token` query parameter
+/// for clients that cannot set headers, like browser WebSockets.
+pub fn request_token(headers: &HeaderMap, query_token: Option<String>) -> Option<String> {
+    headers.get("authorization")
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Bearer "))
+        .map(|x| x.trim().to_string())
+        .or(query_token)
+}
+
+/// Fails with `401` for unknown tokens and `403` when the device's role is too low.
+pub fn authorize(
+    app_handle: &tauri::AppHandle,
+    token: Option<&str>,
+    required_role: RemoteRole,
+) -> Result<RemoteRole, (StatusCode, String)> {
+    let role = app_handle.state::<ProgramState>()
+        .with_state(|state| token.and_then(|token| device_role(&state.config, token)))
+        .ok_or((StatusCode::UNAUTHORIZED, "Device not paired".to_string()))?;
+
+    if role < required_role {
+        return Err((StatusCode::FORBIDDEN, "Not allowed for this device".to_string()));
+    }
+
+    Ok(role)
+}
+
+pub fn device_role(config: &ProgramConfig, token: &str) -> Option<RemoteRole> {
+    config.remote.devices.iter()
+        .find(|x| secrets_match(&x.token, token))
+        .map(|x| x.role)
+}
+
+/// The paired devices, without their tokens.
+fn device_list(config: &ProgramConfig) -> Vec<RemoteDevice> {
+    config.remote.devices.iter()
+        .map(|x| RemoteDevice {
+            token: String::new(),
+            ..x.clone()
+        })
+        .collect()
+}
+
+fn emit_remote_devices(app_handle: &tauri::AppHandle, config: &ProgramConfig) {
+    emit_to(app_handle, "main", "update-remote-devices", device_list(config));
+}
+
+fn emit_remote_pin(app_handle: &tauri::AppHandle, pairing: &RemotePairing) {
+    emit_to(app_handle, "main", "update-remote-pin", &pairing.pin);
+}
+
+
+#[tauri::command]
+pub async fn get_remote_pairing(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(String, RemoteRole), String> { // Ok(pin, role for new devices)
+    Ok(program_state.with_state(|state| (state.remote_pairing.pin.clone(), state.remote_pairing.new_device_role)))
+}
+
+#[tauri::command]
+pub async fn regenerate_remote_pin(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    Ok(program_state.with_state(|state| {
+        let pairing = &mut state.remote_pairing;
+        pairing.pin = generate_pin();
+        pairing.failed_attempts = 0;
+        pairing.next_attempt_at = None;
+
+        emit_remote_pin(&app_handle, pairing);
+        pairing.pin.clone()
+    }))
+}
+
+#[tauri::command]
+pub async fn set_new_device_role(
+    role: RemoteRole,
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(), String> {
+    program_state.with_state(|state| state.remote_pairing.new_device_role = role);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_remote_devices(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<Vec<RemoteDevice>, String> {
+    Ok(program_state.with_state(|state| device_list(&state.config)))
+}
+
+#[tauri::command]
+pub async fn set_remote_device_role(
+    device_id: u64,
+    role: RemoteRole,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        let device = state.config.remote.devices.iter_mut()
+            .find(|x| x.id == device_id)
+            .ok_or("Device not found".to_string())?;
+        device.role = role;
+
+        write_config(&state.config)?;
+        emit_remote_devices(&app_handle, &state.config);
+        Ok(())
+    })
+}
+
+/// The device has to pair again to regain access. Open connections are closed on the next event.
+#[tauri::command]
+pub async fn revoke_remote_device(
+    device_id: u64,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        let devices = &mut state.config.remote.devices;
+        if let Some(device) = devices.iter().find(|x| x.id == device_id) {
+            remove_secret(&device.token);
+        }
+        devices.retain(|x| x.id != device_id);
+
+        write_config(&state.config)?;
+        emit_remote_devices(&app_handle, &state.config);
+        Ok(())
+    })
+}
@@ -0,0 +1,122 @@
+use std::net::{IpAddr, UdpSocket};
+use base64::Engine;
+use qrcode::render::svg;
+use qrcode::{Color, QrCode};
+use serde::{Deserialize, Serialize};
+use crate::ProgramState;
+use crate::remote::RemoteConfig;
+use crate::logging::remove_secret;
+use crate::remote_auth::generate_token;
+
+/// Pixels per QR module in the PNG.
+const PNG_MODULE_SIZE: usize = 8;
+/// Empty modules around the code, as required by the QR spec.
+const QUIET_ZONE: usize = 4;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum QrCodeFormat {
+    Svg,
+    Png,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RemoteQrCode {
+    pub url: String,
+    /// SVG markup, or a `data:image/png;base64,...` URL.
+    pub image: String,
+}
+
+/// The address phones on the network can reach the remote on, for the running server in `AppState::remote`.
+pub fn remote_url(config: &RemoteConfig) -> Result<String, String> {
+    let host = match config.bind_address.parse::<IpAddr>() {
+        Ok(address) if !address.is_unspecified() && !address.is_loopback() => address,
+        _ => local_ip_address().ok_or("Could not find the network address of this computer".to_string())?,
+    };
+
+    Ok(match host {
+        IpAddr::V4(host) => format!("http://{}:{}/", host, config.port),
+        IpAddr::V6(host) => format!("http://[{}]:{}/", host, config.port),
+    })
+}
+
+/// The address of the network interface used for outgoing traffic.
+/// Connecting a UDP socket does not send anything.
+fn local_ip_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+
+    socket.local_addr().ok().map(|x| x.ip())
+}
+
+fn render_svg(code: &QrCode) -> String {
+    code.render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .quiet_zone(true)
+        .build()
+}
+
+fn render_png(code: &QrCode) -> Result<String, String> {
+    let modules = code.width();
+    let size = (modules + 2 * QUIET_ZONE) * PNG_MODULE_SIZE;
+    let colors = code.to_colors();
+
+    let mut pixels = vec![255u8; size * size];
+    for (index, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+
+        let x = (index % modules + QUIET_ZONE) * PNG_MODULE_SIZE;
+        let y = (index / modules + QUIET_ZONE) * PNG_MODULE_SIZE;
+        for row in y..y + PNG_MODULE_SIZE {
+            pixels[row * size + x..row * size + x + PNG_MODULE_SIZE].fill(0);
+        }
+    }
+
+    let mut png_data = vec![];
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, size as u32, size as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|_| "Unable to create PNG".to_string())?;
+        writer.write_image_data(&pixels).map_err(|_| "Unable to create PNG".to_string())?;
+    }
+
+    Ok(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png_data)))
+}
+
+
+/// Creates a QR code with the remote URL and a new one-time pairing token.
+/// Earlier QR codes stop working.
+#[tauri::command]
+pub async fn get_remote_qr_code(
+    format: QrCodeFormat,
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<RemoteQrCode, String> {
+    // In one go, so the token always belongs to the config it was created for
+    let url = program_state.with_state(|state| {
+        if !state.remote.enabled {
+            return Err("Remote control is disabled".to_string());
+        }
+
+        let pairing_token = generate_token();
+        let url = format!("{}?pair={}", remote_url(&state.remote)?, pairing_token);
+        // The earlier QR code stops working
+        if let Some(replaced_token) = state.remote_pairing.pairing_token.replace(pairing_token) {
+            remove_secret(&replaced_token);
+        }
+        Ok(url)
+    })?;
+
+    let code = QrCode::new(url.as_bytes()).map_err(|_| "Unable to create QR code".to_string())?;
+    let image = match format {
+        QrCodeFormat::Svg => render_svg(&code),
+        QrCodeFormat::Png => render_png(&code)?,
+    };
+
+    Ok(RemoteQrCode {
+        url,
+        image,
+    })
+}
@@ -0,0 +1,71 @@
+use beamer_core::session::Session;
+use beamer_core::song::{Song, SongSlotType};
+use crate::ProgramState;
+use crate::auto_advance::follow_live_slot;
+use crate::events::WindowEvents;
+use crate::library::save_to_library;
+
+/// Applies a change to the session, then restarts the timers if the live slot changed.
+fn change_song_list(
+    program_state: &ProgramState,
+    app_handle: &tauri::AppHandle,
+    change: impl FnOnce(&mut Session, &WindowEvents) -> Result<bool, String>,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        let live_slot_changed = change(&mut state.session, &WindowEvents::new(app_handle, &mut state.captions))?;
+        follow_live_slot(state, app_handle, live_slot_changed);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn insert_slot(
+    position: usize,
+    slot: SongSlotType,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    change_song_list(&program_state, &app_handle, |session, events| session.insert_slot(events, position, slot))
+}
+
+#[tauri::command]
+pub async fn move_slot(
+    slot_id: u64,
+    position: usize,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    change_song_list(&program_state, &app_handle, |session, events| session.move_slot(events, slot_id, position))
+}
+
+#[tauri::command]
+pub async fn remove_slot(
+    slot_id: u64,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    change_song_list(&program_state, &app_handle, |session, events| session.remove_slot(events, slot_id))
+}
+
+#[tauri::command]
+pub async fn duplicate_slot(
+    slot_id: u64,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    change_song_list(&program_state, &app_handle, |session, events| session.duplicate_slot(events, slot_id))
+}
+
+#[tauri::command]
+pub async fn replace_slot_song(
+    slot_id: u64,
+    song: Song,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let edited_song = song.clone();
+    change_song_list(&program_state, &app_handle, |session, events| session.replace_slot_song(events, slot_id, song))?;
+
+    save_to_library(&app_handle, &edited_song);
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use tauri::Manager;
+use beamer_core::display_selection::DisplaySelection;
+use beamer_core::song::{SongList, Verse};
+use crate::ProgramState;
+use crate::events::emit_to;
+
+/// What the musicians see on the stage display, sent as `update-stage-display`.
+#[derive(Clone, Debug, Serialize)]
+pub struct StageDisplay {
+    pub current_verse: Verse,
+    /// `None` at the end of the song list.
+    pub next_verse: Option<Verse>,
+    pub section_label: String,
+    pub song_title: String,
+    pub next_song_title: Option<String>,
+}
+
+/// Not tied to the selection, sent as `update-stage-status`.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StageStatus {
+    /// Unix timestamp in milliseconds, the stage display shows the time elapsed since.
+    pub service_started_at: Option<u64>,
+    /// Messages from the operator that have not expired yet.
+    pub messages: Vec<StageMessage>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StageMessage {
+    pub id: u64,
+    pub text: String,
+    /// Unix timestamps in milliseconds.
+    pub sent_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+/// Older messages are dropped from the history.
+const MAX_MESSAGE_HISTORY: usize = 50;
+
+#[derive(Clone, Debug, Default)]
+pub struct StageState {
+    pub status: StageStatus,
+    /// Sent messages, newest first. Only shown to the operator.
+    pub message_history: Vec<StageMessage>,
+    pub new_message_id: u64,
+}
+
+impl StageDisplay {
+    pub fn new(song_list: &SongList, selection: &DisplaySelection, next_selection: &DisplaySelection) -> Self {
+        let next_verse = if next_selection.is_same_slide(selection) {
+            None
+        } else {
+            Some(next_selection.current_verse(song_list))
+        };
+
+        let next_song_title = song_list.songs.iter()
+            .position(|x| x.id == selection.slot_id())
+            .and_then(|x| DisplaySelection::new(song_list, x + 1, None).ok())
+            .map(|x| x.song_title(song_list).to_string());
+
+        Self {
+            current_verse: selection.current_verse(song_list),
+            next_verse,
+            section_label: selection.section_label(song_list),
+            song_title: selection.song_title(song_list).to_string(),
+            next_song_title,
+        }
+    }
+}
+
+/// Only the stage window receives this: the congregation must not see operator messages.
+fn emit_stage_status(app_handle: &tauri::AppHandle, status: &StageStatus) {
+    emit_to(app_handle, "stage", "update-stage-status", status);
+}
+
+pub fn unix_millis() -> Result<u64, String> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_millis() as u64)
+        .map_err(|_| "System time is before 1970".to_string())
+}
+
+fn start_message_expiry(app_handle: tauri::AppHandle, message_id: u64, seconds: u64) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(seconds)).await;
+
+        app_handle.state::<ProgramState>().with_state(|state| {
+            let stage = &mut state.stage;
+
+            let message_count = stage.status.messages.len();
+            stage.status.messages.retain(|x| x.id != message_id);
+
+            // Already dismissed otherwise
+            if stage.status.messages.len() != message_count {
+                emit_stage_status(&app_handle, &stage.status);
+            }
+        });
+    });
+}
+
+
+#[tauri::command]
+pub async fn get_stage_display(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(StageDisplay, StageStatus), String> {
+    Ok(program_state.with_state(|state| {
+        let mut next_selection = state.session.currently_selected;
+        next_selection.next(&state.session.song_list);
+
+        (StageDisplay::new(&state.session.song_list, &state.session.currently_selected, &next_selection), state.stage.status.clone())
+    }))
+}
+
+#[tauri::command]
+pub async fn start_service_timer(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let started_at = unix_millis()?;
+
+    program_state.with_state(|state| {
+        state.stage.status.service_started_at = Some(started_at);
+        emit_stage_status(&app_handle, &state.stage.status);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn reset_service_timer(
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        state.stage.status.service_started_at = None;
+        emit_stage_status(&app_handle, &state.stage.status);
+    });
+    Ok(())
+}
+
+/// Shows a message on the stage display until it expires or is dismissed.
+#[tauri::command]
+pub async fn send_stage_message(
+    text: &str,
+    expires_in_seconds: Option<u64>,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<StageMessage, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("The message is empty".to_string());
+    }
+
+    let sent_at = unix_millis()?;
+    let expires_in_seconds = expires_in_seconds.filter(|x| *x > 0);
+
+    let message = program_state.with_state(|state| {
+        let stage = &mut state.stage;
+
+        let message = StageMessage {
+            id: stage.new_message_id,
+            text: text.to_string(),
+            sent_at,
+            expires_at: expires_in_seconds.map(|x| sent_at + x * 1000),
+        };
+        stage.new_message_id += 1;
+
+        stage.status.messages.push(message.clone());
+        stage.message_history.insert(0, message.clone());
+        stage.message_history.truncate(MAX_MESSAGE_HISTORY);
+
+        emit_stage_status(&app_handle, &stage.status);
+        message
+    });
+
+    if let Some(seconds) = expires_in_seconds {
+        start_message_expiry(app_handle.clone(), message.id, seconds);
+    }
+
+    Ok(message)
+}
+
+#[tauri::command]
+pub async fn dismiss_stage_message(
+    message_id: u64,
+    program_state: tauri::State<'_, ProgramState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    program_state.with_state(|state| {
+        state.stage.status.messages.retain(|x| x.id != message_id);
+        emit_stage_status(&app_handle, &state.stage.status);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_stage_messages(
+    program_state: tauri::State<'_, ProgramState>,
+) -> Result<(Vec<StageMessage>, Vec<StageMessage>), String> { // Ok(active messages, history)
+    Ok(program_state.with_state(|state| (state.stage.status.messages.clone(), state.stage.message_history.clone())))
+}
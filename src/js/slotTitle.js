@@ -0,0 +1,16 @@
+export function slotTitle(songSlot) {
+    const slot = songSlot.slot;
+
+    if (slot.Song !== undefined) {
+        return slot.Song.title;
+    } else if (slot.Image !== undefined) {
+        return slot.Image.title;
+    } else if (slot.CustomSlides !== undefined) {
+        return slot.CustomSlides.title;
+    } else if (slot.Bible !== undefined) {
+        return slot.Bible.reference;
+    } else if (slot.Countdown !== undefined) {
+        return slot.Countdown.label;
+    }
+    return "Empty Slide";
+}
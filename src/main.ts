@@ -12,6 +12,8 @@ import Textarea from 'primevue/textarea';
 import Toast from "primevue/toast";
 import Panel from "primevue/panel";
 import Sidebar from "primevue/sidebar";
+import Dropdown from "primevue/dropdown";
+import Listbox from "primevue/listbox";
 
 import './css/normalize.css';
 // import './css/skeleton.css';
@@ -38,5 +40,7 @@ app.component('OrderList', OrderList);
 app.component('Toast', Toast);
 app.component('Panel', Panel);
 app.component('Sidebar', Sidebar);
+app.component('Dropdown', Dropdown);
+app.component('Listbox', Listbox);
 app.mount('#app');
 
@@ -6,35 +6,45 @@ export const useDisplaySelectionStore = defineStore('displaySelection', {
         currentDisplay: {
             slot_id: 0,
             slot_position: 0,
-            song: {
-                author: "None",
-                title: "Empty Panel",
-                verses: {
-                    lines: [],
-                },
-            },
             verse_num: 0,
+            num_verses: 1,
+            kind: "Empty",
+            title: "Empty Panel",
+            author: "None",
+            verse: {
+                lines: [],
+            },
         },
         nextDisplay: {
             slot_id: 0,
             slot_position: 0,
-            song: {
-                author: "None",
-                title: "Empty Panel",
-                verses: {
-                    lines: [],
-                },
-            },
             verse_num: 0,
+            num_verses: 1,
+            kind: "Empty",
+            title: "Empty Panel",
+            author: "None",
+            verse: {
+                lines: [],
+            },
         },
+        version: 0,
+        isBlank: false,
     }),
     actions: {
         async load() {
             const result: any = await invoke("get_display_selection", {});
 
-            console.log("load result", result);
-            this.currentDisplay = result[0];
-            this.nextDisplay = result[1];
+            this.applyUpdate(result);
+            this.isBlank = await invoke("get_blank", {});
+        },
+        // Returns false if updates were missed since the last one, e.g. while the window was busy.
+        applyUpdate(update: any) {
+            const missedUpdates = this.version != 0 && update.version > this.version + 1;
+
+            this.version = update.version;
+            this.currentDisplay = update.current;
+            this.nextDisplay = update.next;
+            return !missedUpdates;
         },
         async nextVerse() {
             await invoke("next_verse", {});
@@ -42,5 +52,8 @@ export const useDisplaySelectionStore = defineStore('displaySelection', {
         async previousVerse() {
             await invoke("previous_verse", {});
         },
+        async toggleBlank() {
+            await invoke("set_blank", { blank: !this.isBlank });
+        },
     }
 })
@@ -5,11 +5,15 @@ export const useSettingsStore = defineStore('settings', {
     state: () => ({
         geniusToken: "",
         fontSize: "",
+        remoteConfig: { enabled: false, bind_address: "0.0.0.0", port: 7878 },
+        oscConfig: { enabled: false, bind_address: "0.0.0.0", port: 8000, feedback_targets: [] },
     }),
     actions: {
         async load() {
             this.geniusToken = await invoke("get_genius_token", {});
             this.fontSize = await invoke("get_font_size", {});
+            this.remoteConfig = await invoke("get_remote_config", {});
+            this.oscConfig = await invoke("get_osc_config", {});
         },
 
         async setToken(newToken: string) {
@@ -24,6 +28,18 @@ export const useSettingsStore = defineStore('settings', {
             await invoke("save_config", {});
         },
 
+        async setRemoteConfig(newRemoteConfig: any) {
+            this.remoteConfig = newRemoteConfig;
+            await invoke("set_remote_config", { newRemoteConfig: this.remoteConfig });
+            await invoke("save_config", {});
+        },
+
+        async setOscConfig(newOscConfig: any) {
+            this.oscConfig = newOscConfig;
+            await invoke("set_osc_config", { newOscConfig: this.oscConfig });
+            await invoke("save_config", {});
+        },
+
         async saveSettings() {
             await invoke("save_config", {});
         },
@@ -4,9 +4,23 @@ import {invoke} from "@tauri-apps/api";
 export const useSongListStore = defineStore('songList', {
     state: () => ({
         songs: [],
+        // Of the last update-song-list
+        version: 0,
+        undoLabel: null as string | null,
+        redoLabel: null as string | null,
     }),
     actions: {
         async updateBackend(newSongs: any) {
+            const movedSlotId = this.findMovedSlot(newSongs);
+            if (movedSlotId !== null) {
+                // The list is updated by the update-song-list event
+                await invoke("move_slot", {
+                    slotId: movedSlotId,
+                    position: newSongs.findIndex((song: any) => song.id == movedSlotId),
+                });
+                return;
+            }
+
             await invoke("update_song_list", {
                 newSongList: {
                     songs: newSongs,
@@ -14,6 +28,56 @@ export const useSongListStore = defineStore('songList', {
             });
             this.songs = newSongs;
         },
+        findMovedSlot(newSongs: any) {
+            // A reorder that moved a single slot, otherwise null
+            const oldIds = this.songs.map((song: any) => song.id);
+            const newIds = newSongs.map((song: any) => song.id);
+            if (oldIds.length != newIds.length || oldIds.every((id: any, i: number) => id == newIds[i])) {
+                return null;
+            }
+
+            const without = (ids: any[], slotId: any) => ids.filter((id) => id != slotId).join(",");
+            const moved = oldIds.find((slotId: any) => without(oldIds, slotId) == without(newIds, slotId));
+            return moved === undefined ? null : moved;
+        },
+        // Returns false if the whole list has to be loaded again: it was replaced, or updates were missed.
+        applyUpdate(update: any) {
+            const missedUpdates = this.version != 0 && update.version > this.version + 1;
+
+            this.version = update.version;
+            if (update.change === undefined || missedUpdates) {
+                return false;
+            }
+            this.applyChange(update);
+            return true;
+        },
+        applyChange(change: any) {
+            const songs: any[] = [...this.songs];
+            switch (change.change) {
+                case "Inserted":
+                    songs.splice(change.payload.position, 0, change.payload.slot);
+                    break;
+                case "Moved":
+                    songs.splice(change.payload.to, 0, songs.splice(change.payload.from, 1)[0]);
+                    break;
+                case "Removed":
+                    songs.splice(change.payload.position, 1);
+                    break;
+                case "Replaced":
+                    songs[change.payload.position] = change.payload.slot;
+                    break;
+            }
+            this.songs = songs;
+        },
+        async insertEmptySlot(position: number) {
+            await invoke("insert_slot", { position, slot: "Empty" });
+        },
+        async duplicateById(slotId: any) {
+            await invoke("duplicate_slot", { slotId });
+        },
+        async replaceSong(slotId: any, song: any) {
+            await invoke("replace_slot_song", { slotId, song });
+        },
         async removeFirst() {
             const newSongs = this.songs;
             newSongs.shift();
@@ -21,19 +85,50 @@ export const useSongListStore = defineStore('songList', {
             await this.updateBackend(newSongs);
         },
         async removeById(song_id: any) {
-            console.log("song_id", song_id);
-            let newSongs: any = this.songs;
-            newSongs = newSongs.filter((song: any) => song.id != song_id)
-
-            await this.updateBackend(newSongs);
+            await invoke("remove_slot", { slotId: song_id });
         },
         async addSearchedSong(author: string, title: string) {
             const result: any = await invoke('add_searched_song', { author, title });
             this.songs = result.songs;
         },
+        async addLibrarySong(song: any) {
+            const newSongList: any = await invoke("add_library_song", { song });
+            this.songs = newSongList.songs;
+        },
         async addSong(songAddition: any) {
             const newSongList: any = await invoke("add_song", songAddition);
             this.songs = newSongList.songs;
+        },
+        async addImage(sourcePath: string, title: string, scaling: string) {
+            const newSongList: any = await invoke("add_image", { sourcePath, title, scaling });
+            this.songs = newSongList.songs;
+        },
+        async addCustomSlides(customSlidesAddition: any) {
+            const newSongList: any = await invoke("add_custom_slides", customSlidesAddition);
+            this.songs = newSongList.songs;
+        },
+        async addBiblePassage(bibleName: string, reference: string) {
+            const newSongList: any = await invoke("add_bible_passage", { bibleName, reference });
+            this.songs = newSongList.songs;
+        },
+        async addCountdown(countdownAddition: any) {
+            const newSongList: any = await invoke("add_countdown", countdownAddition);
+            this.songs = newSongList.songs;
+        },
+        async undo() {
+            const newSongList: any = await invoke("undo", {});
+            this.songs = newSongList.songs;
+        },
+        async redo() {
+            const newSongList: any = await invoke("redo", {});
+            this.songs = newSongList.songs;
+        },
+        setHistory(history: any) {
+            this.undoLabel = history.undo;
+            this.redoLabel = history.redo;
+        },
+        async loadHistory() {
+            this.setHistory(await invoke("get_history", {}));
         }
     }
 })
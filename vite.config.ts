@@ -26,6 +26,7 @@ export default defineConfig({
       input: {
         main: './index.html',
         presentation: './presentation.html',
+        stage: './stage.html',
       },
     },
   },